@@ -4,8 +4,15 @@ use clap::{ArgGroup, Parser};
 use promptly::{prompt, prompt_default};
 
 use schnauzer_ui::{
-    datatable::read_csv, install_drivers, interpreter::Interpreter, new_driver, parser::Stmt, run,
-    scanner::Scanner, with_drivers_running, SupportedBrowser, WebDriverConfig,
+    config::{read_config, read_vars_file},
+    datatable::read_csv,
+    install_drivers,
+    interpreter::{Interpreter, InterpreterConfig},
+    new_driver,
+    parser::Stmt,
+    run,
+    scanner::Scanner,
+    with_drivers_running, SupportedBrowser, WebDriverConfig,
 };
 
 /// SchnauzerUI is a DSL for automated web UI testing.
@@ -42,16 +49,55 @@ struct Cli {
     #[arg(short = 'x', long)]
     datatable: Option<PathBuf>,
 
+    /// Save a variable before the script runs, as NAME=VALUE. Can be passed more than once.
+    #[arg(long = "var", value_parser = parse_var)]
+    vars: Vec<(String, String)>,
+
+    /// Path to a JSON or TOML file of variables to save before the script runs.
+    /// Variables passed with --var take precedence.
+    #[arg(long)]
+    vars_file: Option<PathBuf>,
+
     /// Highlight elements which are located to more clearly demonstrate process
     #[arg(long)]
     demo: bool,
 
+    /// Record failed assertions without stopping the script, as if every assertion was marked soft.
+    #[arg(long)]
+    soft_assertions: bool,
+
+    /// How many milliseconds if conditions and the exists command look for an element before giving up.
+    /// Defaults to checking once.
+    #[arg(long, default_value_t = 0)]
+    probe_window_ms: u64,
+
     /// "Bring Your Own Drivers". Turns off driver management so you can run against external processes.
     #[arg(long)]
     byod: bool,
 
     #[arg(long)]
     override_port: Option<usize>,
+
+    /// Path to a SchnauzerUI config file. Defaults to sui.toml in the current directory, if it exists.
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Which environment profile from the config file to use (ex. dev, staging, prod).
+    #[arg(short, long)]
+    profile: Option<String>,
+
+    /// Base url that url slugs are resolved against. Overrides the base url of the profile.
+    #[arg(long)]
+    base_url: Option<String>,
+
+    /// Size of the browser window as WIDTHxHEIGHT (ex. 1280x800). Defaults to maximized.
+    #[arg(long, value_parser = parse_window_size)]
+    window_size: Option<(u32, u32)>,
+
+    /// Emulate a mobile device using one of Chrome's device presets (ex. "iPhone 12 Pro").
+    /// Only supported in chrome.
+    #[arg(long)]
+    device: Option<String>,
 }
 
 fn main() {
@@ -94,9 +140,18 @@ async fn start(
         headless,
         browser,
         datatable,
+        vars,
+        vars_file,
         demo,
+        soft_assertions,
+        probe_window_ms,
         byod: _,
         override_port,
+        config,
+        profile,
+        base_url,
+        window_size,
+        device,
     }: Cli,
 ) {
     // Resolve browser to a supported browser
@@ -112,6 +167,11 @@ async fn start(
         }
     };
 
+    if device.is_some() && browser != SupportedBrowser::Chrome {
+        eprintln!("Usage: device emulation is only supported in chrome");
+        return;
+    }
+
     let port = override_port.unwrap_or_else(|| match browser {
         SupportedBrowser::FireFox => 4444,
         SupportedBrowser::Chrome => 9515,
@@ -134,11 +194,51 @@ async fn start(
     // If a path to a datatable was provided, read in the datatable as a csv.
     let dt = datatable.map(|path| read_csv(path));
 
+    // Read in the config file if there is one
+    let config_path = config.or_else(|| {
+        let default_path = PathBuf::from("sui.toml");
+        default_path.is_file().then(|| default_path)
+    });
+    let config = config_path
+        .map(|path| read_config(path))
+        .unwrap_or_default();
+
+    // Resolve the base url, preferring the command line over the profile
+    let profile_base_url = match config.profile(profile.as_deref()) {
+        Some(p) => p.base_url.clone(),
+        None => {
+            if let Some(name) = profile {
+                eprintln!("Could not find profile {} in config file", name);
+                return;
+            }
+            None
+        }
+    };
+    let base_url = base_url.or(profile_base_url);
+
+    // Collect the variables to save before running, preferring the command line over the vars file
+    let mut initial_vars = vars_file
+        .map(|path| read_vars_file(path))
+        .unwrap_or_default();
+    initial_vars.extend(vars);
+
+    // Combine interpreter related arguments into a config object
+    let interpreter_config = InterpreterConfig {
+        is_demo: demo,
+        base_url,
+        soft_assertions,
+        probe_window: std::time::Duration::from_millis(probe_window_ms),
+        vars: initial_vars,
+        ..Default::default()
+    };
+
     // Combine webdriver related arguments into a config object
     let driver_config = WebDriverConfig {
         port,
         headless,
         browser,
+        window_size,
+        device,
     };
 
     // Delegate based on provided cli arguments
@@ -157,13 +257,19 @@ async fn start(
             let output = output_dir
                 .or(filepath.parent().map(|f| f.to_path_buf()))
                 .unwrap_or(".".into());
-            run_file(filepath, output, driver_config, dt, demo).await
+            run_file(filepath, output, driver_config, dt, interpreter_config).await
         }
 
         // They provided the repl flag, so run in repl mode.
         // The output directory should default to the current directory.
         (None, true) => {
-            if let Err(e) = repl_loop(output_dir.unwrap_or(".".into()), driver_config, demo).await {
+            if let Err(e) = repl_loop(
+                output_dir.unwrap_or(".".into()),
+                driver_config,
+                interpreter_config,
+            )
+            .await
+            {
                 eprintln!("REPL encountered an error: {}", e);
             }
         }
@@ -179,7 +285,7 @@ async fn run_file(
     output_filepath: PathBuf,
     driver_config: WebDriverConfig,
     dt: Option<Vec<HashMap<String, String>>>,
-    is_demo: bool,
+    mut config: InterpreterConfig,
 ) {
     // Read in the file
     let code = std::fs::read_to_string(input_filepath.clone()).expect(&format!(
@@ -189,13 +295,15 @@ async fn run_file(
 
     let file_name = get_filename_as_string(&input_filepath);
 
+    config.script_dir = input_filepath.parent().map(|dir| dir.to_path_buf());
+
     // Create a driver
     let driver = new_driver(driver_config)
         .await
         .expect("Could not launch driver");
 
     // Run the code
-    run(code, output_filepath, file_name, driver, dt, is_demo)
+    run(code, output_filepath, file_name, driver, dt, config)
         .await
         .expect("Oh no!");
 }
@@ -203,12 +311,12 @@ async fn run_file(
 async fn repl_loop(
     output_filepath: PathBuf,
     driver_config: WebDriverConfig,
-    is_demo: bool,
+    config: InterpreterConfig,
 ) -> Result<(), &'static str> {
     let driver = new_driver(driver_config)
         .await
         .map_err(|_| "Error starting interpreter and/or browser")?;
-    let mut interpreter = Interpreter::new(driver, vec![], is_demo, None);
+    let mut interpreter = Interpreter::new(driver, vec![], config, None);
 
     let mut script_buffer = String::new();
 
@@ -237,14 +345,17 @@ async fn repl_loop(
                 }
                 _ => {}
             }
-            script_buffer.push_str(&format!("{}", stmt));
-            script_buffer.push('\n');
-            match interpreter.execute_stmt(stmt).await {
+            match interpreter.execute_stmt(stmt.clone()).await {
                 Ok(_) => {}
                 Err(_) => {
                     println!("Warning: Error encountered while running start script.");
                 }
             }
+            // Save the statement after running it, so any secret it saved is masked
+            if let Some(line) = script_line(&interpreter, &stmt) {
+                script_buffer.push_str(&line);
+                script_buffer.push('\n');
+            }
         }
     }
 
@@ -270,31 +381,41 @@ async fn repl_loop(
         for stmt in stmts.into_iter() {
             match interpreter.execute_stmt(stmt.clone()).await {
                 Ok(_) => {
-                    // Prompt the user if they want to save the statement
-                    let save_stmt: bool = prompt_default("Save this statement?", true)
-                        .map_err(|_| "Error reading in line")?;
-                    if save_stmt {
-                        script_buffer.push_str(&format!("{}", stmt));
-                        script_buffer.push('\n');
-                        match stmt {
-                            Stmt::Comment(_) => {
-                                script_buffer.push('\n');
+                    if let Some(line) = script_line(&interpreter, &stmt) {
+                        // Prompt the user if they want to save the statement
+                        let save_stmt: bool = prompt_default("Save this statement?", true)
+                            .map_err(|_| "Error reading in line")?;
+                        if save_stmt {
+                            script_buffer.push_str(&line);
+                            script_buffer.push('\n');
+                            match stmt {
+                                Stmt::Comment(_) => {
+                                    script_buffer.push('\n');
+                                }
+                                _ => {}
                             }
-                            _ => {}
                         }
                     }
                 }
                 Err(e) => {
-                    eprintln!("The statement {} resulted in an error: {}", stmt, e.0);
-                    let save_stmt: bool = prompt_default("Save this statement anyway?", false)
-                        .map_err(|_| "Error reading in line")?;
-                    if save_stmt {
-                        script_buffer.push_str(&format!("{}", stmt));
-                        script_buffer.push('\n');
-                        match stmt {
-                            Stmt::Comment(_) => {}
-                            _ => {
-                                script_buffer.push('\n');
+                    let e = interpreter.redact_error(e);
+                    eprintln!(
+                        "The statement {} resulted in an error ({}): {}",
+                        interpreter.redact(&stmt.to_string()),
+                        e.kind,
+                        e
+                    );
+                    if let Some(line) = script_line(&interpreter, &stmt) {
+                        let save_stmt: bool = prompt_default("Save this statement anyway?", false)
+                            .map_err(|_| "Error reading in line")?;
+                        if save_stmt {
+                            script_buffer.push_str(&line);
+                            script_buffer.push('\n');
+                            match stmt {
+                                Stmt::Comment(_) => {}
+                                _ => {
+                                    script_buffer.push('\n');
+                                }
                             }
                         }
                     }
@@ -323,6 +444,44 @@ async fn repl_loop(
 
 // Helpers ---------------------
 
+/// The text of a statement to write to the script the REPL records, with secrets masked.
+/// A secret written out in the statement can't be saved, since the masked script would
+/// use "***" as the secret when it's run again.
+fn script_line(interpreter: &Interpreter, stmt: &Stmt) -> Option<String> {
+    match stmt {
+        Stmt::SetVariable(sv) if sv.has_literal_secret() => {
+            eprintln!(
+                "Warning: Not saving a secret written into the script. Use `save secret env \"<NAME>\" as {}` \
+                 and set the environment variable (or add it to a .env file) instead.",
+                sv.variable_name
+            );
+            None
+        }
+        _ => Some(interpreter.redact(&stmt.to_string())),
+    }
+}
+
+fn parse_window_size(size: &str) -> Result<(u32, u32), String> {
+    let invalid = || {
+        format!(
+            "Expected a window size like 1280x800, but received {}",
+            size
+        )
+    };
+    let (width, height) = size.split_once('x').ok_or_else(invalid)?;
+    Ok((
+        width.trim().parse().map_err(|_| invalid())?,
+        height.trim().parse().map_err(|_| invalid())?,
+    ))
+}
+
+fn parse_var(var: &str) -> Result<(String, String), String> {
+    let (name, value) = var
+        .split_once('=')
+        .ok_or_else(|| format!("Variables must look like NAME=VALUE, but received {}", var))?;
+    Ok((name.trim().to_owned(), value.to_owned()))
+}
+
 fn get_filename_as_string(path: &PathBuf) -> String {
     path.file_stem()
         .expect("Could not get file name")
@@ -336,3 +495,26 @@ fn looks_like_file(path: &PathBuf) -> bool {
         .unwrap_or("".to_owned())
         .contains(".")
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_window_sizes() {
+        assert_eq!(parse_window_size("1280x800"), Ok((1280, 800)));
+        assert!(parse_window_size("1280").is_err());
+        assert!(parse_window_size("widexhigh").is_err());
+        assert!(parse_window_size("1280x").is_err());
+    }
+
+    #[test]
+    fn parses_vars() {
+        assert_eq!(
+            parse_var("url=https://example.com/?a=b"),
+            Ok(("url".to_owned(), "https://example.com/?a=b".to_owned()))
+        );
+        assert_eq!(parse_var("empty="), Ok(("empty".to_owned(), "".to_owned())));
+        assert!(parse_var("username").is_err());
+    }
+}
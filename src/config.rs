@@ -0,0 +1,143 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use serde::Deserialize;
+
+/// Settings read from a SchnauzerUI config file (sui.toml).
+/// # Example
+/// ```toml
+/// default_profile = "dev"
+///
+/// [profiles.dev]
+/// base_url = "http://localhost:3000"
+///
+/// [profiles.staging]
+/// base_url = "https://staging.example.com"
+/// ```
+#[derive(Debug, Default, Deserialize)]
+pub struct Config {
+    /// The profile to use when one isn't passed on the command line
+    pub default_profile: Option<String>,
+
+    /// Named environment profiles, like dev, staging and prod
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// Settings for running scripts against a specific environment.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Profile {
+    /// The url slugs passed to the url command are resolved against
+    pub base_url: Option<String>,
+}
+
+impl Config {
+    /// Looks up a profile by name, falling back to the default profile
+    /// if no name is provided.
+    pub fn profile(&self, name: Option<&str>) -> Option<&Profile> {
+        name.or(self.default_profile.as_deref())
+            .and_then(|name| self.profiles.get(name))
+    }
+}
+
+pub fn read_config(path: PathBuf) -> Config {
+    let contents = std::fs::read_to_string(&path)
+        .expect(&format!("Could not read config file {}", path.display()));
+    toml::from_str(&contents).expect(&format!("Could not parse config file {}", path.display()))
+}
+
+/// Reads variables to save before a script runs from a JSON file (if it ends in .json) or
+/// a TOML file. The file should be a flat table of names to values. Numbers and booleans
+/// are saved as text, the same way values from a datatable are.
+/// # Example
+/// ```toml
+/// username = "admin@test.com"
+/// tenant = "acme"
+/// ```
+pub fn read_vars_file(path: PathBuf) -> HashMap<String, String> {
+    let contents = std::fs::read_to_string(&path)
+        .expect(&format!("Could not read vars file {}", path.display()));
+    let is_json = path.extension().map_or(false, |ext| ext == "json");
+
+    let vars: Result<HashMap<String, String>, String> = if is_json {
+        serde_json::from_str::<HashMap<String, serde_json::Value>>(&contents)
+            .map_err(|e| e.to_string())
+            .and_then(|vars| {
+                vars.into_iter()
+                    .map(|(name, value)| match value {
+                        serde_json::Value::String(s) => Ok((name, s)),
+                        serde_json::Value::Number(n) => Ok((name, n.to_string())),
+                        serde_json::Value::Bool(b) => Ok((name, b.to_string())),
+                        _ => Err(format!("{} must be text, a number or a boolean", name)),
+                    })
+                    .collect()
+            })
+    } else {
+        toml::from_str::<HashMap<String, toml::Value>>(&contents)
+            .map_err(|e| e.to_string())
+            .and_then(|vars| {
+                vars.into_iter()
+                    .map(|(name, value)| match value {
+                        toml::Value::String(s) => Ok((name, s)),
+                        toml::Value::Integer(n) => Ok((name, n.to_string())),
+                        toml::Value::Float(n) => Ok((name, n.to_string())),
+                        toml::Value::Boolean(b) => Ok((name, b.to_string())),
+                        _ => Err(format!("{} must be text, a number or a boolean", name)),
+                    })
+                    .collect()
+            })
+    };
+
+    vars.unwrap_or_else(|e| panic!("Could not parse vars file {}: {}", path.display(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Writes a vars file to a temporary directory and reads it, catching the panic
+    /// for a file that can't be used.
+    fn read(name: &str, contents: &str) -> std::thread::Result<HashMap<String, String>> {
+        let path = std::env::temp_dir().join(format!("sui-test-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        let vars = std::panic::catch_unwind(|| read_vars_file(path.clone()));
+        std::fs::remove_file(&path).unwrap();
+        vars
+    }
+
+    #[test]
+    fn reads_scalars_as_text() {
+        let vars = read(
+            "scalars.toml",
+            "user = \"admin\"\nretries = 3\nrate = 1.5\nfast = true\n",
+        )
+        .unwrap();
+        assert_eq!(vars["user"], "admin");
+        assert_eq!(vars["retries"], "3");
+        assert_eq!(vars["rate"], "1.5");
+        assert_eq!(vars["fast"], "true");
+
+        let vars = read(
+            "scalars.json",
+            r#"{"user": "admin", "retries": 3, "fast": false}"#,
+        )
+        .unwrap();
+        assert_eq!(vars["user"], "admin");
+        assert_eq!(vars["retries"], "3");
+        assert_eq!(vars["fast"], "false");
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        assert!(read("malformed.toml", "user = ").is_err());
+        assert!(read("malformed.json", r#"{"user": "admin""#).is_err());
+    }
+
+    #[test]
+    fn nested_values_are_rejected() {
+        assert!(read("table.toml", "[login]\nuser = \"admin\"\n").is_err());
+        assert!(read("array.toml", "users = [\"admin\"]\n").is_err());
+        assert!(read("object.json", r#"{"login": {"user": "admin"}}"#).is_err());
+        assert!(read("array.json", r#"{"users": ["admin"]}"#).is_err());
+        assert!(read("null.json", r#"{"user": null}"#).is_err());
+    }
+}
@@ -1,23 +1,287 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
+
+use crate::parser::BinaryOp;
+
+/// The value of a variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+}
+
+impl Value {
+    /// The value as a number, if it is one or is text which can be read as one.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Number(n) => Some(*n),
+            // Words like "nan" and "inf" parse as floats, but aren't numbers in a script
+            Value::Text(t) if t.chars().any(|c| c.is_ascii_digit()) => t.trim().parse().ok(),
+            Value::Text(_) => None,
+            Value::Bool(_) => None,
+        }
+    }
+
+    /// Both values as numbers, when at least one of them is a number. Text which reads as a
+    /// number (like text read from the page) only counts as one next to a number, so text
+    /// like "007" and "7" stays different text.
+    pub fn as_numbers(&self, other: &Value) -> Option<(f64, f64)> {
+        if !matches!(self, Value::Number(_)) && !matches!(other, Value::Number(_)) {
+            return None;
+        }
+        Some((self.as_number()?, other.as_number()?))
+    }
+
+    /// Applies an operator to two values. Adding is arithmetic when the values are numbers,
+    /// otherwise it joins the values as text. The other operators only work on numbers.
+    pub fn apply(self, op: BinaryOp, rhs: Value) -> Result<Value, String> {
+        let (l, r) = match self.as_numbers(&rhs) {
+            Some((l, r)) => (l, r),
+            _ if op == BinaryOp::Add => return Ok(Value::Text(format!("{}{}", self, rhs))),
+            _ => {
+                return Err(format!(
+                    "Can't use {} on {} and {}, they must be numbers",
+                    op, self, rhs
+                ))
+            }
+        };
+        match op {
+            BinaryOp::Add => Ok(Value::Number(l + r)),
+            BinaryOp::Subtract => Ok(Value::Number(l - r)),
+            BinaryOp::Multiply => Ok(Value::Number(l * r)),
+            BinaryOp::Divide if r == 0.0 => Err(format!("Can't divide {} by zero", l)),
+            BinaryOp::Divide => Ok(Value::Number(l / r)),
+        }
+    }
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Text(t) => write!(f, "{}", t),
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+        }
+    }
+}
+
+/// Secrets shorter than this many characters are only masked where they stand on their own,
+/// so a pin like "12" doesn't mask part of "120".
+pub const MIN_SECRET_LEN: usize = 4;
 
 /// Represents the "state" of the programs execution. Basically
 /// keeps track of variables and their values.
-pub struct Environment(HashMap<String, String>);
+pub struct Environment {
+    values: HashMap<String, Value>,
+
+    /// The text of every value saved as a secret, which is masked wherever the script is reported
+    secrets: Vec<String>,
+}
 
 impl Environment {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            values: HashMap::new(),
+            secrets: vec![],
+        }
     }
 
-    /// Set a variable value. SchnauzerUI makes no distinction between
+    /// Set a variable to some text. SchnauzerUI makes no distinction between
     /// declaration and instantiation.
     pub fn set_variable(&mut self, name: String, value: String) {
-        self.0.insert(name, value);
+        self.set_value(name, Value::Text(value));
     }
 
-    /// Get the value of a variable if it exists, or None
+    /// Set a variable to a value of any type.
+    pub fn set_value(&mut self, name: String, value: Value) {
+        self.values.insert(name, value);
+    }
+
+    /// Set a variable to a value which should never show up in reports. The value stays
+    /// masked even if the variable is later saved over.
+    pub fn set_secret(&mut self, name: String, value: Value) {
+        let text = value.to_string();
+        if !text.is_empty() && !self.secrets.contains(&text) {
+            self.secrets.push(text);
+            // Mask longer secrets first, so a secret containing another is masked entirely
+            self.secrets.sort_by(|a, b| b.len().cmp(&a.len()));
+        }
+        self.set_value(name, value);
+    }
+
+    /// Get the value of a variable as text if it exists, or None
     /// if it does not.
     pub fn get_variable(&self, name: &str) -> Option<String> {
-        self.0.get(name).map(|s| s.clone())
+        self.values.get(name).map(|v| v.to_string())
+    }
+
+    /// Get the value of a variable if it exists, or None
+    /// if it does not.
+    pub fn get_value(&self, name: &str) -> Option<Value> {
+        self.values.get(name).cloned()
+    }
+
+    /// Replaces every secret value in some text with `***`. Secrets shorter than
+    /// MIN_SECRET_LEN are only replaced where they aren't part of a longer word or number.
+    pub fn redact(&self, text: &str) -> String {
+        self.secrets.iter().fold(text.to_owned(), |text, secret| {
+            if secret.chars().count() >= MIN_SECRET_LEN {
+                text.replace(secret, "***")
+            } else {
+                replace_whole(&text, secret)
+            }
+        })
+    }
+}
+
+/// Replaces each occurrence of a secret with `***`, unless it's next to a letter or digit.
+fn replace_whole(text: &str, secret: &str) -> String {
+    let is_word = |c: Option<char>| c.map_or(false, |c| c.is_alphanumeric());
+    let mut redacted = String::new();
+    let mut rest = 0;
+    for (start, _) in text.match_indices(secret) {
+        let end = start + secret.len();
+        if is_word(text[..start].chars().next_back()) || is_word(text[end..].chars().next()) {
+            continue;
+        }
+        redacted.push_str(&text[rest..start]);
+        redacted.push_str("***");
+        rest = end;
+    }
+    redacted.push_str(&text[rest..]);
+    redacted
+}
+
+/// Reads the variables defined in a `.env` file. Each line is `NAME=value`, optionally
+/// starting with `export`. Blank lines and lines starting with `#` are ignored,
+/// and quotes around a value are removed.
+pub fn read_dotenv(path: &Path) -> std::io::Result<HashMap<String, String>> {
+    let contents = std::fs::read_to_string(path)?;
+    let mut vars = HashMap::new();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        if let Some((name, value)) = line.split_once('=') {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            vars.insert(name.trim().to_owned(), value.to_owned());
+        }
+    }
+    Ok(vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(t: &str) -> Value {
+        Value::Text(t.to_owned())
+    }
+
+    #[test]
+    fn adding_numeric_text_to_a_number_adds() {
+        assert_eq!(
+            text("10").apply(BinaryOp::Add, Value::Number(5.0)),
+            Ok(Value::Number(15.0))
+        );
+        assert_eq!(
+            text(" 2.5 ").apply(BinaryOp::Add, Value::Number(1.0)),
+            Ok(Value::Number(3.5))
+        );
+    }
+
+    #[test]
+    fn adding_other_values_joins_them() {
+        assert_eq!(
+            text("order-").apply(BinaryOp::Add, Value::Number(7.0)),
+            Ok(text("order-7"))
+        );
+        assert_eq!(
+            text("nan").apply(BinaryOp::Add, text("1")),
+            Ok(text("nan1"))
+        );
+        assert_eq!(
+            Value::Bool(true).apply(BinaryOp::Add, text("!")),
+            Ok(text("true!"))
+        );
+    }
+
+    #[test]
+    fn text_that_reads_as_numbers_stays_text() {
+        assert_eq!(
+            text("555").apply(BinaryOp::Add, text("1234")),
+            Ok(text("5551234"))
+        );
+        assert_eq!(text("007").as_numbers(&text("7")), None);
+        assert_eq!(
+            text("007").as_numbers(&Value::Number(7.0)),
+            Some((7.0, 7.0))
+        );
+        assert!(text("10").apply(BinaryOp::Subtract, text("4")).is_err());
+    }
+
+    #[test]
+    fn secrets_are_masked() {
+        let mut env = Environment::new();
+        env.set_secret("pw".to_owned(), text("hunter2"));
+        assert_eq!(
+            env.redact("typed hunter2 twice: hunter2"),
+            "typed *** twice: ***"
+        );
+    }
+
+    #[test]
+    fn short_secrets_are_masked_where_they_stand_alone() {
+        let mut env = Environment::new();
+        env.set_secret("pin".to_owned(), text("12"));
+        assert_eq!(
+            env.redact("typed 12, page 1 of 120"),
+            "typed ***, page 1 of 120"
+        );
+        assert_eq!(env.redact("12"), "***");
+    }
+
+    #[test]
+    fn other_operators_need_numbers() {
+        assert_eq!(
+            Value::Number(6.0).apply(BinaryOp::Divide, text("4")),
+            Ok(Value::Number(1.5))
+        );
+        assert!(text("abc")
+            .apply(BinaryOp::Multiply, Value::Number(2.0))
+            .is_err());
+        assert!(Value::Number(1.0)
+            .apply(BinaryOp::Divide, Value::Number(0.0))
+            .is_err());
+    }
+
+    #[test]
+    fn reads_dotenv_files() {
+        let path = std::env::temp_dir().join(format!("sui-test-{}.env", std::process::id()));
+        std::fs::write(
+            &path,
+            "# login details\n\nUSERNAME=admin\nexport PASSWORD=\"hunter2 two\"\n  TOKEN = 'abc=def'  \nnot a variable\n",
+        )
+        .unwrap();
+        let vars = read_dotenv(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let vars = vars.unwrap();
+        assert_eq!(vars.len(), 3);
+        assert_eq!(vars["USERNAME"], "admin");
+        assert_eq!(vars["PASSWORD"], "hunter2 two");
+        assert_eq!(vars["TOKEN"], "abc=def");
+    }
+
+    #[test]
+    fn missing_dotenv_files_are_an_error() {
+        assert!(read_dotenv(Path::new("/no/such/dir/.env")).is_err());
     }
 }
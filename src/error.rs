@@ -0,0 +1,173 @@
+use serde::{Deserialize, Serialize};
+use thirtyfour::error::WebDriverError;
+
+/// Represent the Severity of an error within the interpreter (i.e. how to respond to an error).
+/// On a Recoverable error, the script will go to the next catch-error: stmt.
+/// On an Exit error, the interpret method will early return.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Exit,
+    Recoverable,
+}
+
+/// Type alias for errors in the interpreter.
+pub type RuntimeResult<T> = Result<T, RuntimeError>;
+
+/// What went wrong while executing a statement. Written to the json report
+/// so failures can be grouped without reading the error message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorKind {
+    /// No element matched a locator, or no element is currently located
+    ElementNotFound,
+
+    /// The element was found, but can't be used the way the command needs
+    /// (hidden, covered by another element, disabled, wrong kind of element)
+    NotInteractable,
+
+    /// The element was removed from the page after it was located
+    StaleElement,
+
+    /// Something didn't happen in the time we were willing to wait
+    Timeout,
+
+    /// A check in the script didn't hold. The checks are assert-alert-text and exists.
+    AssertionFailed,
+
+    /// The browser window or the webdriver session is gone
+    DriverLost,
+
+    /// A value passed to a command doesn't make sense for it
+    InvalidArgument,
+
+    /// A variable was used before it was saved
+    UndefinedVariable,
+
+    /// An alert command was used while no alert was open
+    NoAlert,
+
+    /// Javascript run in the page threw an error
+    Javascript,
+
+    /// Reading or writing a file failed
+    Io,
+
+    Other,
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ErrorKind::ElementNotFound => "element-not-found",
+            ErrorKind::NotInteractable => "not-interactable",
+            ErrorKind::StaleElement => "stale-element",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::AssertionFailed => "assertion-failed",
+            ErrorKind::DriverLost => "driver-lost",
+            ErrorKind::InvalidArgument => "invalid-argument",
+            ErrorKind::UndefinedVariable => "undefined-variable",
+            ErrorKind::NoAlert => "no-alert",
+            ErrorKind::Javascript => "javascript",
+            ErrorKind::Io => "io",
+            ErrorKind::Other => "other",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl From<&WebDriverError> for ErrorKind {
+    fn from(e: &WebDriverError) -> Self {
+        match e {
+            WebDriverError::NoSuchElement(..) | WebDriverError::NotFound(..) => {
+                ErrorKind::ElementNotFound
+            }
+            WebDriverError::ElementNotInteractable(..)
+            | WebDriverError::ElementClickIntercepted(..)
+            | WebDriverError::ElementNotSelectable(..)
+            | WebDriverError::InvalidElementState(..)
+            | WebDriverError::MoveTargetOutOfBounds(..) => ErrorKind::NotInteractable,
+            WebDriverError::StaleElementReference(..) => ErrorKind::StaleElement,
+            WebDriverError::Timeout(..) | WebDriverError::ScriptTimeout(..) => ErrorKind::Timeout,
+            WebDriverError::NoSuchAlert(..) => ErrorKind::NoAlert,
+            WebDriverError::JavascriptError(..) => ErrorKind::Javascript,
+            WebDriverError::InvalidArgument(..) | WebDriverError::InvalidSelector(..) => {
+                ErrorKind::InvalidArgument
+            }
+            WebDriverError::NoSuchWindow(..)
+            | WebDriverError::SessionNotCreated(..)
+            | WebDriverError::RequestFailed(..) => ErrorKind::DriverLost,
+            _ => ErrorKind::Other,
+        }
+    }
+}
+
+/// An error produced while executing a statement.
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+
+    /// Human readable description of what the interpreter was trying to do
+    pub message: String,
+
+    /// The webdriver error that caused this error, if there was one
+    pub source: Option<WebDriverError>,
+
+    /// The text of the statement being executed
+    pub stmt: Option<String>,
+
+    /// The line of the script the statement came from
+    pub line: Option<usize>,
+
+    pub severity: Severity,
+}
+
+impl RuntimeError {
+    pub fn new(kind: ErrorKind, message: &str, severity: Severity) -> Self {
+        Self {
+            kind,
+            message: message.to_owned(),
+            source: None,
+            stmt: None,
+            line: None,
+            severity,
+        }
+    }
+
+    /// Wraps an error returned by the webdriver, classifying it by the webdriver error.
+    pub fn from_driver(source: WebDriverError, message: &str, severity: Severity) -> Self {
+        Self {
+            kind: ErrorKind::from(&source),
+            source: Some(source),
+            ..Self::new(ErrorKind::Other, message, severity)
+        }
+    }
+
+    /// Records the statement (and its line) the error happened in,
+    /// unless it has already been recorded.
+    pub fn in_stmt(mut self, stmt: &str, line: Option<usize>) -> Self {
+        if self.stmt.is_none() {
+            self.stmt = Some(stmt.to_owned());
+        }
+        if self.line.is_none() {
+            self.line = line;
+        }
+        self
+    }
+}
+
+impl std::fmt::Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.source {
+            Some(ref source) => write!(f, "{}: {}", self.message, source),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for RuntimeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
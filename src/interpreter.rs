@@ -1,25 +1,79 @@
-use std::path::PathBuf;
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
 use async_recursion::async_recursion;
 use futures::TryFutureExt;
-use thirtyfour::{components::SelectElement, prelude::*};
+use thirtyfour::{components::SelectElement, prelude::*, Cookie};
 
+pub use crate::error::{RuntimeResult, Severity};
 use crate::{
-    environment::Environment,
-    parser::{Cmd, CmdParam, CmdStmt, IfStmt, SetVariableStmt, Stmt},
-    test_report::{ExecutedStmt, Report},
+    environment::{read_dotenv, Environment, Value},
+    error::{ErrorKind, RuntimeError},
+    keys::{expand_key_tokens, key_from_name},
+    parser::{
+        AlertPolicy, Cmd, CmdParam, CmdStmt, Comparison, Condition, Expr, IfStmt, Operand,
+        SetVariableStmt, Stmt,
+    },
+    session::{SavedCookie, SavedSession},
+    test_report::{ExecutedStmt, Report, SoftFailure},
 };
 
-/// Represent the Severity of an error within the interpreter (i.e. how to respond to an error).
-/// On a Recoverable error, the script will go to the next catch-error: stmt.
-/// On an Exit error, the interpret method will early return.
-pub enum Severity {
-    Exit,
-    Recoverable,
+/// Options which change how the interpreter runs a script.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InterpreterConfig {
+    /// Highlight elements which are located to more clearly demonstrate process
+    pub is_demo: bool,
+
+    /// The directory of the script being run. Relative file paths (like those passed
+    /// to upload) are resolved against this directory first.
+    pub script_dir: Option<PathBuf>,
+
+    /// Base url that url slugs (like "/login") are resolved against.
+    pub base_url: Option<String>,
+
+    /// Record failed assertions without stopping the script, as if every
+    /// assertion was marked soft.
+    pub soft_assertions: bool,
+
+    /// How long if conditions and the exists command look for an element before giving up.
+    /// By default, they only check once.
+    pub probe_window: Duration,
+
+    /// Variables saved before the script starts, like those passed with --var
+    pub vars: HashMap<String, String>,
+}
+
+/// Progress of a try-again command.
+struct Retry {
+    /// Which attempt is currently running, starting from 1
+    attempt: usize,
+
+    /// How many times the statements will be tried again
+    max_attempts: usize,
+}
+
+/// A try block the interpreter is inside of.
+struct TryBlock {
+    /// An error in the block which hasn't been handled by an on-error section yet.
+    /// It is raised again at the end of the block.
+    error: Option<RuntimeError>,
 }
 
-/// Type alias for errors in the interpreter.
-pub type RuntimeResult<T, E> = Result<T, (E, Severity)>;
+/// A handler declared with whenever.
+struct Handler {
+    /// Locates the element the handler deals with
+    locator: CmdParam,
+
+    /// The commands run against the element
+    cs: CmdStmt,
+
+    /// Whether the handler already ran for the element on the page. It doesn't
+    /// run again until the element has gone away.
+    fired: bool,
+}
 
 /// The interpreter is responsible for executing Schnauzer UI stmts against a running selenium grid.
 pub struct Interpreter {
@@ -32,6 +86,10 @@ pub struct Interpreter {
     /// Each interpreter gets an environment for storing variables
     environment: Environment,
 
+    /// Variables from the .env files next to the script and in the current directory,
+    /// used by env when the environment variable isn't set
+    dotenv: HashMap<String, String>,
+
     /// The locate command brings an element into focus. That element is stored here. Subsequents commands are performed
     /// against this element.
     curr_elem: Option<WebElement>,
@@ -47,19 +105,66 @@ pub struct Interpreter {
     /// the try-again command to be able to re-execute them.
     stmts_since_last_error_handling: Vec<Stmt>,
 
-    /// The tried again field stores whether or not we are in try-again mode. It is used to cause an early return
-    /// in the case that we encounter an error while in try-again mode.
-    tried_again: bool,
+    /// The progress of try-again while it is re-executing statements. It is used to cause an early return
+    /// in the case that we encounter an error during the last attempt.
+    retry: Option<Retry>,
+
+    /// The catch-error stmt currently handling an error. try-again queues it up again
+    /// after the statements it re-executes, in case they fail again.
+    catch_stmt: Option<CmdStmt>,
+
+    /// An attempt of try-again which should be started in the report, once the
+    /// catch-error stmt that asked for it has been reported.
+    pending_attempt: Option<String>,
+
+    /// Whether the next catch-error stmt is one try-again queued up again. If the attempt
+    /// succeeds, it's passed over without handling anything, so it isn't reported twice.
+    replayed_catch: bool,
+
+    /// The try blocks we are currently inside of, innermost last.
+    try_blocks: Vec<TryBlock>,
+
+    /// While skipping the rest of a section of a try block, the number
+    /// of nested try blocks we are skipping over.
+    skip_depth: Option<usize>,
+
+    /// Whether failed assertions are soft failures, even without the soft modifier
+    soft_assertions: bool,
+
+    /// Errors which were recorded without stopping the script
+    soft_failures: Vec<RuntimeError>,
+
+    /// Whether we are checking a condition, in which case commands
+    /// don't wait before running, and locate only checks for the probe window.
+    probing: bool,
+
+    /// How long to check for an element while probing
+    probe_window: Duration,
+
+    /// Handlers declared with whenever, checked between statements
+    handlers: Vec<Handler>,
 
     /// The progress of the program is stored into a buffer to optionally be written to a file
     pub reporter: Option<Report>,
     pub screenshot_buf: Vec<Vec<u8>>,
 
+    /// Notes about how the current statement was executed, added to the report
+    notes_buf: Vec<String>,
+
     /// Denotes whether the program is in "demo" mode
     is_demo: bool,
 
     /// Base for when the under command is used
     under_element: Option<WebElement>,
+
+    /// Directory relative file paths are resolved against
+    script_dir: Option<PathBuf>,
+
+    /// How to respond to alerts the script wasn't expecting
+    alert_policy: AlertPolicy,
+
+    /// Base url for resolving url slugs
+    base_url: Option<String>,
 }
 
 impl Interpreter {
@@ -67,24 +172,58 @@ impl Interpreter {
     pub fn new(
         driver: WebDriver,
         stmts: Vec<Stmt>,
-        is_demo: bool,
+        InterpreterConfig {
+            is_demo,
+            script_dir,
+            base_url,
+            soft_assertions,
+            probe_window,
+            vars,
+        }: InterpreterConfig,
         reporter: Option<Report>,
     ) -> Self {
         let stmts = stmts.into_iter().rev().collect();
 
+        // Read the .env file in the current directory, and then the one next to
+        // the script, so the script's own values win
+        let mut dotenv = read_dotenv(Path::new(".env")).unwrap_or_default();
+        if let Some(dir) = script_dir.as_ref() {
+            dotenv.extend(read_dotenv(&dir.join(".env")).unwrap_or_default());
+        }
+
+        let mut environment = Environment::new();
+        for (name, value) in vars {
+            environment.set_variable(name, value);
+        }
+
         Self {
             driver,
             stmts,
-            environment: Environment::new(),
+            environment,
+            dotenv,
             curr_elem: None,
             had_error: false,
             stmts_since_last_error_handling: vec![],
-            tried_again: false,
+            retry: None,
+            catch_stmt: None,
+            pending_attempt: None,
+            replayed_catch: false,
+            try_blocks: vec![],
+            skip_depth: None,
+            soft_assertions,
+            soft_failures: vec![],
+            probing: false,
+            probe_window,
+            handlers: vec![],
             reporter,
             screenshot_buf: vec![],
+            notes_buf: vec![],
             is_demo,
             locator: None,
             under_element: None,
+            script_dir,
+            alert_policy: AlertPolicy::Ignore,
+            base_url,
         }
     }
 
@@ -94,39 +233,120 @@ impl Interpreter {
         self.curr_elem = None;
         self.had_error = false;
         self.stmts_since_last_error_handling.clear();
-        self.tried_again = false;
+        self.retry = None;
+        self.catch_stmt = None;
+        self.pending_attempt = None;
+        self.replayed_catch = false;
+        self.try_blocks.clear();
+        self.skip_depth = None;
+        self.soft_failures.clear();
+        self.handlers.clear();
+        self.alert_policy = AlertPolicy::Ignore;
 
         while let Some(stmt) = self.stmts.pop() {
-            match self.execute_stmt(stmt.clone()).await {
+            // After an error in a try block, skip the rest of the section
+            if self.skip_stmt(&stmt) {
+                continue;
+            }
+
+            // Give whenever handlers a chance to deal with anything that popped up
+            if !self.had_error
+                && matches!(
+                    stmt,
+                    Stmt::Cmd(_)
+                        | Stmt::If(_)
+                        | Stmt::Under(..)
+                        | Stmt::UnderActiveElement(_)
+                        | Stmt::Soft(_)
+                )
+            {
+                self.run_handlers().await;
+            }
+
+            // A catch-error stmt queued up again by try-again was already reported,
+            // so it's only reported again if it has an error to handle.
+            let replayed_catch = matches!(stmt, Stmt::CatchErr(_))
+                && std::mem::take(&mut self.replayed_catch)
+                && !self.had_error;
+
+            let res = self.execute_stmt(stmt.clone()).await;
+
+            // Markers inserted by the interpreter aren't part of the script, and try blocks
+            // are reported as groups, so none of these are reported as statements.
+            if replayed_catch
+                || matches!(
+                    stmt,
+                    Stmt::SetTryAgainFieldToFalse
+                        | Stmt::Try
+                        | Stmt::OnError
+                        | Stmt::Finally
+                        | Stmt::End
+                )
+            {
+                // An error the try block didn't handle is raised again at its end
+                if let Err(e) = res {
+                    if self.propagate_error(e) {
+                        break;
+                    }
+                }
+                continue;
+            }
+
+            match res {
                 Ok(()) => {
+                    let executed = self.executed_stmt(&stmt, None);
                     if let Some(ref mut reporter) = self.reporter {
-                        reporter.add_stmt(ExecutedStmt {
-                            text: stmt.to_string(),
-                            error: None,
-                            screenshots: std::mem::replace(&mut self.screenshot_buf, vec![]),
-                        });
+                        reporter.add_stmt(executed);
                     }
                 }
-                Err((e, sev)) => {
+                Err(e) => {
+                    let e = self.redact_error(e.in_stmt(&stmt.to_string(), stmt.line()));
+
+                    // Soft failures are recorded, but the script carries on as if nothing happened.
+                    // Errors which end the script (like the last attempt of try-again) are never soft.
+                    let is_soft = e.severity != Severity::Exit
+                        && (matches!(stmt, Stmt::Soft(_))
+                            || (self.soft_assertions && e.kind == ErrorKind::AssertionFailed));
+                    if is_soft {
+                        self.notes_buf
+                            .push("Soft failure, the script continued".to_owned());
+                    }
+
+                    // report the error
+                    let executed = self.executed_stmt(&stmt, Some(&e));
                     if let Some(ref mut reporter) = self.reporter {
-                        // report the error
-                        reporter.add_stmt(ExecutedStmt {
-                            text: stmt.to_string(),
-                            error: Some(e),
-                            screenshots: std::mem::replace(&mut self.screenshot_buf, vec![]),
-                        });
+                        reporter.add_stmt(executed);
                     }
 
-                    match sev {
-                        Severity::Exit => {
-                            break;
-                        }
-                        Severity::Recoverable => {
-                            self.had_error = true;
+                    if is_soft {
+                        let text = self.redact(&stmt.to_string());
+                        if let Some(ref mut reporter) = self.reporter {
+                            reporter.soft_failures.push(SoftFailure {
+                                text,
+                                error: e.to_string(),
+                                error_kind: e.kind,
+                                line: e.line,
+                            });
                         }
+                        self.soft_failures.push(e);
+                    } else if self.propagate_error(e) {
+                        break;
                     }
                 }
             }
+
+            // Statements re-executed by try-again are nested under their attempt
+            if let Some(attempt) = self.pending_attempt.take() {
+                if let Some(ref mut reporter) = self.reporter {
+                    reporter.begin_group(attempt);
+                }
+            }
+        }
+
+        // Close the attempt of try-again (or try block) we exited during, if any
+        self.retry = None;
+        if let Some(ref mut reporter) = self.reporter {
+            reporter.end_all_groups();
         }
 
         // We completed the entire script.
@@ -134,18 +354,169 @@ impl Interpreter {
             self.driver.close_window().await?;
         }
 
-        // Return whether or not we exited the program while inn error mode.
-        Ok(self.had_error)
+        // Return whether or not we exited the program while inn error mode,
+        // or had soft failures along the way.
+        Ok(self.had_error || !self.soft_failures.is_empty())
     }
 
-    /// Produces an error with the appropriate severity based on
-    /// whether we are currently trying to execute stmts again.
-    fn error(&self, msg: &str) -> (String, Severity) {
-        if self.tried_again {
-            (msg.to_owned(), Severity::Exit)
-        } else {
-            (msg.to_owned(), Severity::Recoverable)
+    /// Errors which were recorded without stopping the script during the last run.
+    pub fn soft_failures(&self) -> &[RuntimeError] {
+        &self.soft_failures
+    }
+
+    /// Runs the command statement of each whenever handler whose element has appeared on the page.
+    /// A handler runs once each time its element appears. Handlers don't change which element
+    /// is in focus, and an error in a handler is reported without stopping the script.
+    async fn run_handlers(&mut self) {
+        for i in 0..self.handlers.len() {
+            let locator = self.handlers[i].locator.clone();
+            let cs = self.handlers[i].cs.clone();
+            let resolved = match self.resolve(locator.clone()) {
+                Ok(resolved) => resolved,
+                Err(_) => continue,
+            };
+
+            // Handlers are checked before every statement, so a single script checks whether
+            // the element is there, and it's only located once the handler is going to run.
+            if !self.appears(&resolved).await {
+                self.handlers[i].fired = false;
+                continue;
+            }
+            if self.handlers[i].fired {
+                continue;
+            }
+            let found_elem = match self.find_once(&resolved).await {
+                Some(found_elem) => found_elem,
+                None => continue,
+            };
+            self.handlers[i].fired = true;
+
+            let curr_elem = self.curr_elem.clone();
+            let last_locator = self.locator.clone();
+            let res = match self.set_curr_elem(found_elem, false).await {
+                Ok(_) => self.execute_cmd_stmt(cs.clone()).await,
+                Err(e) => Err(e),
+            };
+            self.curr_elem = curr_elem;
+            self.locator = last_locator;
+
+            let stmt = Stmt::Whenever(locator, cs);
+            let e = res.err().map(|e| self.redact_error(e));
+            self.notes_buf
+                .push("Ran because the element appeared".to_owned());
+            let executed = self.executed_stmt(&stmt, e.as_ref());
+            if let Some(ref mut reporter) = self.reporter {
+                reporter.add_stmt(executed);
+            }
+        }
+    }
+
+    /// Builds the report entry for a statement which just ran, taking the screenshots
+    /// and notes collected while it ran. Secrets are masked in everything but the screenshots.
+    fn executed_stmt(&mut self, stmt: &Stmt, error: Option<&RuntimeError>) -> ExecutedStmt {
+        let notes = std::mem::replace(&mut self.notes_buf, vec![]);
+        ExecutedStmt {
+            text: self.redact(&stmt.to_string()),
+            error: error.map(|e| self.redact(&e.to_string())),
+            error_kind: error.map(|e| e.kind),
+            line: error.and_then(|e| e.line).or(stmt.line()),
+            screenshots: std::mem::replace(&mut self.screenshot_buf, vec![]),
+            notes: notes.iter().map(|note| self.redact(note)).collect(),
+            children: vec![],
+        }
+    }
+
+    /// Replaces the value of every secret saved so far in some text with `***`.
+    pub fn redact(&self, text: &str) -> String {
+        self.environment.redact(text)
+    }
+
+    /// Masks secrets in an error's message and statement.
+    pub fn redact_error(&self, mut e: RuntimeError) -> RuntimeError {
+        let full = e.to_string();
+        let redacted = self.redact(&full);
+        if redacted != full {
+            // The webdriver error can't be edited, so it's folded into the message instead
+            e.message = redacted;
+            e.source = None;
+        }
+        e.stmt = e.stmt.map(|stmt| self.redact(&stmt));
+        e
+    }
+
+    /// Hands an error to the innermost try block, or (outside of try blocks) moves on to
+    /// the next catch-error stmt. Returns whether the script should exit.
+    fn propagate_error(&mut self, e: RuntimeError) -> bool {
+        match self.try_blocks.last_mut() {
+            Some(block) => {
+                // An error which ends the script takes precedence over other errors in the block,
+                // so it is still raised after the finally section.
+                if !matches!(block.error, Some(ref prev) if prev.severity == Severity::Exit) {
+                    block.error = Some(e);
+                }
+                self.skip_depth = Some(0);
+                false
+            }
+            None => match e.severity {
+                Severity::Exit => true,
+                Severity::Recoverable => {
+                    self.had_error = true;
+                    false
+                }
+            },
+        }
+    }
+
+    /// While skipping the rest of a section of a try block, decides whether to skip a stmt.
+    /// Skipping stops at the next on-error, finally or end stmt of the block, and try blocks
+    /// nested in the section are skipped entirely.
+    fn skip_stmt(&mut self, stmt: &Stmt) -> bool {
+        let depth = match self.skip_depth {
+            Some(depth) => depth,
+            None => return false,
+        };
+
+        match stmt {
+            Stmt::Try => self.skip_depth = Some(depth + 1),
+            Stmt::End if depth > 0 => self.skip_depth = Some(depth - 1),
+            Stmt::OnError | Stmt::Finally | Stmt::End => {
+                self.skip_depth = None;
+                return false;
+            }
+            _ => {}
         }
+
+        // Skipped stmts can still be re-executed by try-again
+        self.remember_stmt(stmt);
+        true
+    }
+
+    /// Adds a stmt to the list of stmts since the last catch-error stmt was encountered.
+    /// Used by the try-again command to re-execute on an error.
+    fn remember_stmt(&mut self, stmt: &Stmt) {
+        if !matches!(stmt, Stmt::CatchErr(_) | Stmt::SetTryAgainFieldToFalse) {
+            self.stmts_since_last_error_handling.push(stmt.clone());
+        }
+    }
+
+    /// The severity of errors right now, based on
+    /// whether we are currently on the last attempt of try-again.
+    fn severity(&self) -> Severity {
+        match self.retry {
+            Some(ref retry) if retry.attempt >= retry.max_attempts => Severity::Exit,
+            _ => Severity::Recoverable,
+        }
+    }
+
+    /// Produces an error with the appropriate severity.
+    fn error(&self, kind: ErrorKind, msg: &str) -> RuntimeError {
+        RuntimeError::new(kind, msg, self.severity())
+    }
+
+    /// Produces an error with the appropriate severity from an error returned by the webdriver.
+    /// The webdriver error is kept as the source, and decides the kind of the error.
+    fn driver_error(&self, source: WebDriverError, msg: &str) -> RuntimeError {
+        RuntimeError::from_driver(source, msg, self.severity())
     }
 
     /// Takes a webelement, attempts to scroll the element into view, and then sets
@@ -154,7 +525,7 @@ impl Interpreter {
         &mut self,
         elem: WebElement,
         scroll_into_view: bool,
-    ) -> RuntimeResult<WebElement, String> {
+    ) -> RuntimeResult<WebElement> {
         // Scroll the element into view if specified, but don't fail on an error
         // as this can error falsely for thing like chat windows
         if scroll_into_view {
@@ -170,10 +541,10 @@ impl Interpreter {
             "#,
                     vec![elem
                         .to_json()
-                        .map_err(|_| self.error("Error jsonifying element"))?],
+                        .map_err(|e| self.driver_error(e, "Error jsonifying element"))?],
                 )
                 .await
-                .map_err(|_| self.error("Error highlighting element"))?;
+                .map_err(|e| self.driver_error(e, "Error highlighting element"))?;
 
             // Remove the border from the previously located element
             if let Some(ref curr_elem) = self.curr_elem {
@@ -187,7 +558,7 @@ impl Interpreter {
             "#,
                         vec![curr_elem
                             .to_json()
-                            .map_err(|_| self.error("Error jsonifying element"))?],
+                            .map_err(|e| self.driver_error(e, "Error jsonifying element"))?],
                     )
                     .await;
             }
@@ -200,12 +571,12 @@ impl Interpreter {
 
     /// Returns a reference to the current element for performing operations on, or an
     /// error if there is no current element.
-    async fn get_curr_elem(&mut self) -> RuntimeResult<&WebElement, String> {
+    async fn get_curr_elem(&mut self) -> RuntimeResult<&WebElement> {
         if let Some(elem) = self.curr_elem.as_ref() {
             if !elem
                 .is_present()
                 .await
-                .map_err(|_| self.error("Error checking if element is present"))?
+                .map_err(|e| self.driver_error(e, "Error checking if element is present"))?
             {
                 // Element is stale, so replay the last locate command. Helps with pages which are highly dynamic
                 // for a few moments during the loading.
@@ -215,26 +586,22 @@ impl Interpreter {
             }
         }
 
-        self.curr_elem
-            .as_ref()
-            .ok_or(self.error("No element currently located. Try using the locate command"))
+        self.curr_elem.as_ref().ok_or(self.error(
+            ErrorKind::ElementNotFound,
+            "No element currently located. Try using the locate command",
+        ))
     }
 
     /// Executes a single SchnauzerUI statement.
-    pub async fn execute_stmt(&mut self, stmt: Stmt) -> RuntimeResult<(), String> {
-        // Add the statement to the list of stmts since the last catch-error stmt was encountered.
-        // Used by the try-again command to re-execute on an error.
-        self.stmts_since_last_error_handling.push(stmt.clone());
+    pub async fn execute_stmt(&mut self, stmt: Stmt) -> RuntimeResult<()> {
+        self.remember_stmt(&stmt);
 
         if !self.had_error {
             // Normal Execution
             match stmt {
                 Stmt::Cmd(cs) => self.execute_cmd_stmt(cs).await,
                 Stmt::If(is) => self.execute_if_stmt(is).await,
-                Stmt::SetVariable(sv) => {
-                    self.set_variable(sv);
-                    Ok(())
-                }
+                Stmt::SetVariable(sv) => self.set_variable(sv),
                 Stmt::Comment(_) => {
                     // Comments are simply added to the report log, so we just ignore them
                     Ok(())
@@ -247,9 +614,9 @@ impl Interpreter {
                 }
                 Stmt::SetTryAgainFieldToFalse => {
                     // This command was inserted by the interpreter as part of executing try-again.
-                    // Reaching this command means the second attempt passed without erroring,
+                    // Reaching this command means the attempt passed without erroring,
                     // so we go back to normal execution mode.
-                    self.tried_again = false;
+                    self.end_attempt();
                     Ok(())
                 }
                 Stmt::Under(cp, cs) => {
@@ -258,12 +625,64 @@ impl Interpreter {
                     self.under_element = None;
                     Ok(())
                 }
+                Stmt::OnAlert(policy) => {
+                    self.alert_policy = policy;
+                    Ok(())
+                }
+                Stmt::Soft(cs) => self.execute_cmd_stmt(cs).await,
+                Stmt::Whenever(cp, cs) => {
+                    // A new handler for the same locator replaces the old one
+                    self.handlers.retain(|handler| handler.locator != cp);
+                    self.handlers.push(Handler {
+                        locator: cp,
+                        cs,
+                        fired: false,
+                    });
+                    Ok(())
+                }
+                Stmt::Try => {
+                    self.try_blocks.push(TryBlock { error: None });
+                    if let Some(ref mut reporter) = self.reporter {
+                        reporter.begin_group("try".to_owned());
+                    }
+                    Ok(())
+                }
+                Stmt::OnError => {
+                    // The on-error section handles errors from the try section, but not
+                    // errors which end the script. Otherwise, skip ahead to finally or end.
+                    if let Some(block) = self.try_blocks.last_mut() {
+                        match block.error {
+                            Some(ref e) if e.severity == Severity::Recoverable => {
+                                block.error = None;
+                            }
+                            _ => self.skip_depth = Some(0),
+                        }
+                    }
+                    Ok(())
+                }
+                Stmt::Finally => {
+                    // Always runs, so there's nothing to do
+                    Ok(())
+                }
+                Stmt::End => {
+                    let block = match self.try_blocks.pop() {
+                        Some(block) => block,
+                        None => return Ok(()),
+                    };
+                    if let Some(ref mut reporter) = self.reporter {
+                        reporter.end_group();
+                    }
+                    match block.error {
+                        Some(e) => Err(e),
+                        None => Ok(()),
+                    }
+                }
                 Stmt::UnderActiveElement(cs) => {
                     let active_elm = self
                         .driver
                         .active_element()
                         .await
-                        .map_err(|_| self.error("Error getting active element."))?;
+                        .map_err(|e| self.driver_error(e, "Error getting active element."))?;
                     self.under_element = Some(active_elm);
                     self.execute_cmd_stmt(cs).await?;
                     self.under_element = None;
@@ -274,16 +693,30 @@ impl Interpreter {
             // Syncronizing after an error.
             match stmt {
                 Stmt::CatchErr(cs) => {
+                    // If an attempt of try-again failed, it ends here.
+                    if let Some(ref mut reporter) = self.reporter {
+                        if self.retry.is_some() {
+                            reporter.end_group();
+                        }
+                    }
+
                     // Execute the commands on the catch-error line.
-                    self.execute_cmd_stmt(cs).await?;
+                    self.catch_stmt = Some(cs.clone());
+                    let res = self.execute_cmd_stmt(cs).await;
+                    self.catch_stmt = None;
+                    res?;
+
+                    // Unless the catch-error stmt tried again, we're done retrying.
+                    if self.pending_attempt.is_none() {
+                        self.retry = None;
+                    }
 
                     // Exit error mode and continue normal operation.
                     self.had_error = false;
                     Ok(())
                 }
-                stmt => {
-                    // Read in the rest of the stmts until catch-error for possible re-execution.
-                    self.stmts_since_last_error_handling.push(stmt);
+                _ => {
+                    // The rest of the stmts until catch-error were already read in for possible re-execution.
                     Ok(())
                 }
             }
@@ -296,22 +729,113 @@ impl Interpreter {
         SetVariableStmt {
             variable_name,
             value,
+            secret,
+            ..
         }: SetVariableStmt,
-    ) {
-        self.environment.set_variable(variable_name, value);
+    ) -> RuntimeResult<()> {
+        let value = self.evaluate(value)?;
+        if secret {
+            self.environment.set_secret(variable_name, value);
+        } else {
+            self.environment.set_value(variable_name, value);
+        }
+        Ok(())
+    }
+
+    /// Computes the value of the expression in a save statement.
+    pub fn evaluate(&self, expr: Expr) -> RuntimeResult<Value> {
+        match expr {
+            Expr::Text(t) => Ok(Value::Text(t)),
+            Expr::Number(n) => Ok(Value::Number(n)),
+            Expr::Bool(b) => Ok(Value::Bool(b)),
+            Expr::Variable(name) => self.environment.get_value(&name).ok_or(self.error(
+                ErrorKind::UndefinedVariable,
+                &format!("Variable {} is not yet defined", name),
+            )),
+            Expr::Binary(lhs, op, rhs) => {
+                let lhs = self.evaluate(*lhs)?;
+                let rhs = self.evaluate(*rhs)?;
+                lhs.apply(op, rhs)
+                    .map_err(|msg| self.error(ErrorKind::InvalidArgument, &msg))
+            }
+            Expr::Upper(e) => Ok(Value::Text(self.evaluate(*e)?.to_string().to_uppercase())),
+            Expr::Lower(e) => Ok(Value::Text(self.evaluate(*e)?.to_string().to_lowercase())),
+            Expr::Trim(e) => Ok(Value::Text(
+                self.evaluate(*e)?.to_string().trim().to_owned(),
+            )),
+            Expr::Substring(e, start, end) => {
+                let text = self.evaluate(*e)?.to_string();
+                let start = self.index(*start)?;
+                let end = self.index(*end)?;
+                if start > end {
+                    return Err(self.error(
+                        ErrorKind::InvalidArgument,
+                        &format!("Substring start {} is after its end {}", start, end),
+                    ));
+                }
+                Ok(Value::Text(
+                    text.chars().skip(start).take(end - start).collect(),
+                ))
+            }
+            Expr::Match(pattern, e) => {
+                let pattern = self.evaluate(*pattern)?.to_string();
+                let text = self.evaluate(*e)?.to_string();
+                let re = regex::Regex::new(&pattern).map_err(|e| {
+                    self.error(
+                        ErrorKind::InvalidArgument,
+                        &format!("Invalid regex {}: {}", pattern, e),
+                    )
+                })?;
+                let caps = re.captures(&text).ok_or(self.error(
+                    ErrorKind::Other,
+                    &format!("No match for {} in {}", pattern, text),
+                ))?;
+                // Use the first capture group if there is one, otherwise the whole match
+                let m = caps
+                    .get(1)
+                    .or(caps.get(0))
+                    .map(|m| m.as_str())
+                    .unwrap_or("");
+                Ok(Value::Text(m.to_owned()))
+            }
+            Expr::Env(name) => {
+                let name = self.evaluate(*name)?.to_string();
+                std::env::var(&name)
+                    .ok()
+                    .or(self.dotenv.get(&name).cloned())
+                    .map(Value::Text)
+                    .ok_or(self.error(
+                        ErrorKind::UndefinedVariable,
+                        &format!("Environment variable {} is not set", name),
+                    ))
+            }
+        }
+    }
+
+    /// Evaluates an expression used as a substring index.
+    fn index(&self, expr: Expr) -> RuntimeResult<usize> {
+        let value = self.evaluate(expr)?;
+        match value.as_number() {
+            Some(n) if n >= 0.0 && n.fract() == 0.0 => Ok(n as usize),
+            _ => Err(self.error(
+                ErrorKind::InvalidArgument,
+                &format!("{} is not a valid index", value),
+            )),
+        }
     }
 
     /// Tries to retrieve the value of a variable.
-    pub fn get_variable(&self, name: &str) -> RuntimeResult<String, String> {
-        self.environment
-            .get_variable(name)
-            .ok_or(self.error("Variable is not yet defined"))
+    pub fn get_variable(&self, name: &str) -> RuntimeResult<String> {
+        self.environment.get_variable(name).ok_or(self.error(
+            ErrorKind::UndefinedVariable,
+            &format!("Variable {} is not yet defined", name),
+        ))
     }
 
     /// Takes a cmd_param and tries to resolve it to a string. If it's a user provided String literal, just
     /// returns the value of the string. If it's a variable name, tries to retrieve the variable
     /// from the interpreters environment.
-    pub fn resolve(&self, cmd_param: CmdParam) -> RuntimeResult<String, String> {
+    pub fn resolve(&self, cmd_param: CmdParam) -> RuntimeResult<String> {
         match cmd_param {
             CmdParam::String(s) => Ok(s),
             CmdParam::Variable(v) => self.get_variable(&v),
@@ -326,18 +850,78 @@ impl Interpreter {
             condition,
             then_branch,
         }: IfStmt,
-    ) -> RuntimeResult<(), String> {
-        if self.execute_cmd(condition).await.is_ok() {
+    ) -> RuntimeResult<()> {
+        // Conditions are usually checking for optional things like popups, so
+        // we don't want them waiting around.
+        self.probing = true;
+        let res = self.check_condition(condition).await;
+        self.probing = false;
+
+        if res? {
             self.execute_cmd_stmt(then_branch).await
         } else {
             Ok(())
         }
     }
 
+    /// Decides whether a condition holds. A command holds if it succeeds, and comparisons and element states
+    /// don't hold if the element can't be found. Using a variable which isn't defined is still an error.
+    #[async_recursion]
+    pub async fn check_condition(&mut self, condition: Condition) -> RuntimeResult<bool> {
+        match condition {
+            Condition::Cmd(cmd) => Ok(self.execute_cmd(cmd).await.is_ok()),
+            Condition::Compare(lhs, cmp, rhs) => {
+                let (lhs, rhs) = match (self.operand(lhs).await?, self.operand(rhs).await?) {
+                    (Some(lhs), Some(rhs)) => (lhs, rhs),
+                    _ => return Ok(false),
+                };
+                Ok(match cmp {
+                    // Text is compared to a number as a number, so "3.0" equals 3,
+                    // but two pieces of text are compared as text
+                    Comparison::Equals => match lhs.as_numbers(&rhs) {
+                        Some((l, r)) => l == r,
+                        _ => lhs.to_string() == rhs.to_string(),
+                    },
+                    Comparison::Contains => lhs.to_string().contains(&rhs.to_string()),
+                })
+            }
+            Condition::IsEnabled(cp) => match self.locate(cp, false).await {
+                Ok(elem) => Ok(elem.is_enabled().await.unwrap_or(false)),
+                Err(e) if e.kind == ErrorKind::UndefinedVariable => Err(e),
+                Err(_) => Ok(false),
+            },
+            Condition::IsDisplayed(cp) => match self.locate(cp, false).await {
+                Ok(elem) => Ok(elem.is_displayed().await.unwrap_or(false)),
+                Err(e) if e.kind == ErrorKind::UndefinedVariable => Err(e),
+                Err(_) => Ok(false),
+            },
+            Condition::Not(c) => Ok(!self.check_condition(*c).await?),
+            Condition::And(lhs, rhs) => {
+                Ok(self.check_condition(*lhs).await? && self.check_condition(*rhs).await?)
+            }
+            Condition::Or(lhs, rhs) => {
+                Ok(self.check_condition(*lhs).await? || self.check_condition(*rhs).await?)
+            }
+        }
+    }
+
+    /// Resolves an operand of a comparison to a value. Returns None if
+    /// the element for a text-of operand can't be found.
+    async fn operand(&mut self, operand: Operand) -> RuntimeResult<Option<Value>> {
+        match operand {
+            Operand::Expr(e) => self.evaluate(e).map(Some),
+            Operand::TextOf(cp) => match self.locate(cp, false).await {
+                Ok(elem) => Ok(elem.text().await.ok().map(Value::Text)),
+                Err(e) if e.kind == ErrorKind::UndefinedVariable => Err(e),
+                Err(_) => Ok(None),
+            },
+        }
+    }
+
     /// Execute each cmd until there are no more combining `and` tokens.
     /// Fail early if one command fails.
     #[async_recursion]
-    pub async fn execute_cmd_stmt(&mut self, cs: CmdStmt) -> RuntimeResult<(), String> {
+    pub async fn execute_cmd_stmt(&mut self, cs: CmdStmt) -> RuntimeResult<()> {
         self.execute_cmd(cs.lhs).await?;
         if let Some((_, rhs)) = cs.rhs {
             self.execute_cmd_stmt(*rhs).await
@@ -346,21 +930,34 @@ impl Interpreter {
         }
     }
 
-    pub async fn execute_cmd(&mut self, cmd: Cmd) -> RuntimeResult<(), String> {
+    pub async fn execute_cmd(&mut self, cmd: Cmd) -> RuntimeResult<()> {
         // Adding a default wait of 1 second between commands because it just mimics human timing a lot
-        // better. Will add a flag to turn this off.
-        tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        // better. Will add a flag to turn this off. Conditions skip the wait.
+        if !self.probing {
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        }
+
+        // Alert commands expect an alert to be open, so only handle unexpected alerts
+        // before other commands.
+        if !matches!(
+            cmd,
+            Cmd::AcceptAlert
+                | Cmd::DismissAlert
+                | Cmd::ReadAlertTo(_)
+                | Cmd::TypeIntoAlert(_)
+                | Cmd::AssertAlertText(_)
+        ) {
+            self.handle_unexpected_alert().await;
+        }
 
         match cmd {
             Cmd::Locate(locator) => self.locate(locator, true).await.map(|_| ()),
             Cmd::LocateNoScroll(locator) => self.locate(locator, false).await.map(|_| ()),
+            Cmd::Exists(locator) => self.exists(locator).await,
             Cmd::Type(txt) => self.type_into_elem(txt).await,
             Cmd::Click => self.click().await,
             Cmd::Refresh => self.refresh().await,
-            Cmd::TryAgain => {
-                self.try_again();
-                Ok(())
-            }
+            Cmd::TryAgain(attempts, delay) => self.try_again(attempts, delay).await,
             Cmd::Screenshot => self.screenshot().await,
             Cmd::ReadTo(cp) => self.read_to(cp).await,
             Cmd::Url(url) => self.url_cmd(url).await,
@@ -368,19 +965,61 @@ impl Interpreter {
             Cmd::Chill(cp) => self.chill(cp).await,
             Cmd::Select(cp) => self.select(cp).await,
             Cmd::DragTo(cp) => self.drag_to(cp).await,
-            Cmd::Upload(cp) => self.upload(cp).await,
+            Cmd::Upload(cps) => self.upload(cps).await,
             Cmd::AcceptAlert => {
                 self.driver
                     .accept_alert()
-                    .map_err(|_| self.error("Error accepting alert"))
+                    .map_err(|e| self.driver_error(e, "Error accepting alert"))
                     .await
             }
             Cmd::DismissAlert => {
                 self.driver
                     .dismiss_alert()
-                    .map_err(|_| self.error("Error dismissing alert"))
+                    .map_err(|e| self.driver_error(e, "Error dismissing alert"))
                     .await
             }
+            Cmd::Hover => self.hover().await,
+            Cmd::DoubleClick => self.double_click().await,
+            Cmd::RightClick => self.right_click().await,
+            Cmd::ClickAt(x, y) => self.click_at(x, y).await,
+            Cmd::Check => self.set_checked(true).await,
+            Cmd::Uncheck => self.set_checked(false).await,
+            Cmd::Toggle => self.click().await,
+            Cmd::Append(cp) => self.append(cp).await,
+            Cmd::TypeSlowly(cp) => self.type_slowly(cp).await,
+            Cmd::Clear => self.clear().await,
+            Cmd::SelectValue(cp) => self.select_value(cp).await,
+            Cmd::SelectIndex(cp) => self.select_index(cp).await,
+            Cmd::DeselectAll => self.deselect_all().await,
+            Cmd::DragBy(x, y) => self.drag_by(x, y).await,
+            Cmd::ReadAlertTo(name) => self.read_alert_to(name).await,
+            Cmd::TypeIntoAlert(cp) => self.type_into_alert(cp).await,
+            Cmd::AssertAlertText(cp) => self.assert_alert_text(cp).await,
+            Cmd::Back => self.back().await,
+            Cmd::Forward => self.forward().await,
+            Cmd::WaitForUrl(cp) => self.wait_for_url(cp).await,
+            Cmd::SetCookie(name, value) => self.set_cookie(name, value).await,
+            Cmd::DeleteCookies => self.delete_cookies().await,
+            Cmd::ReadCookie(name, var) => self.read_cookie(name, var).await,
+            Cmd::SetLocalStorage(key, value) => {
+                self.set_storage_item("localStorage", key, value).await
+            }
+            Cmd::ReadLocalStorage(key, var) => {
+                self.read_storage_item("localStorage", key, var).await
+            }
+            Cmd::SetSessionStorage(key, value) => {
+                self.set_storage_item("sessionStorage", key, value).await
+            }
+            Cmd::ReadSessionStorage(key, var) => {
+                self.read_storage_item("sessionStorage", key, var).await
+            }
+            Cmd::SaveSession(cp) => self.save_session(cp).await,
+            Cmd::LoadSession(cp) => self.load_session(cp).await,
+            Cmd::ExecuteJs(cp, var) => self.execute_js(cp, var).await,
+            Cmd::ScrollToBottom => self.scroll_to_bottom().await,
+            Cmd::ScrollBy(x, y) => self.scroll_by(x, y).await,
+            Cmd::ScrollIntoView(cp) => self.scroll_into_view(cp).await,
+            Cmd::SetWindowSize(w, h) => self.set_window_size(w, h).await,
         }
     }
 
@@ -391,7 +1030,7 @@ impl Interpreter {
     // A label/input pair with the matching for/id attributes respectively,
     // or a label/input pair where the label element contains the input element or directly precedes it,
     // will be swapped.
-    async fn resolve_label(&mut self) -> RuntimeResult<(), String> {
+    async fn resolve_label(&mut self) -> RuntimeResult<()> {
         // Label with correct for attribute
         if self
             .get_curr_elem()
@@ -423,7 +1062,7 @@ impl Interpreter {
                 .await?
                 .attr("for")
                 .await
-                .map_err(|_| self.error("Unknown error"))?;
+                .map_err(|e| self.driver_error(e, "Unknown error"))?;
 
             // Try to find the input element with the corresponding id or name attribute
             if let Some(for_attr) = for_attr {
@@ -467,38 +1106,360 @@ impl Interpreter {
         Ok(())
     }
 
-    pub async fn upload(&mut self, cp: CmdParam) -> RuntimeResult<(), String> {
+    pub async fn upload(&mut self, cps: Vec<CmdParam>) -> RuntimeResult<()> {
         // Uploading to a file input is the same as typing keys into it,
         // but our users shouldn't have to know that.
 
-        let path_str = self.resolve(cp)?;
-        let path = PathBuf::from(path_str);
-        let abs_path = path
-            .canonicalize()
-            .map_err(|_| self.error("Error resolving path to file"))?;
-        let abs_path_str = abs_path
-            .to_str()
-            .ok_or(self.error("Error converting absolute path to string"))?;
+        let mut abs_paths = vec![];
+        for cp in cps.into_iter() {
+            let path = self.resolve_path(self.resolve(cp)?);
+            let abs_path = path.canonicalize().map_err(|_| {
+                self.error(
+                    ErrorKind::Io,
+                    &format!("Error resolving path to file {}", path.display()),
+                )
+            })?;
+            let abs_path_str = abs_path
+                .to_str()
+                .ok_or(self.error(
+                    ErrorKind::InvalidArgument,
+                    "Error converting absolute path to string",
+                ))?
+                .to_owned();
+            abs_paths.push(abs_path_str);
+        }
 
-        self.get_curr_elem()
+        // Multiple files are sent to a file input as newline separated paths
+        self.find_file_input()
             .await?
-            .send_keys(abs_path_str)
+            .send_keys(abs_paths.join("\n"))
+            .await
+            .map_err(|e| self.driver_error(e, "Error uploading file"))
+    }
+
+    /// Resolves a relative path against the directory of the script being run. If there is
+    /// no such file next to the script, the path is left relative to the current directory.
+    fn resolve_path(&self, path_str: String) -> PathBuf {
+        let path = PathBuf::from(path_str);
+        if path.is_relative() {
+            if let Some(ref script_dir) = self.script_dir {
+                let relative_to_script = script_dir.join(&path);
+                if relative_to_script.exists() {
+                    return relative_to_script;
+                }
+            }
+        }
+        path
+    }
+
+    /// Resolves a relative path against the directory of the script being run (if there is one),
+    /// for files the script is creating.
+    fn script_relative_path(&self, path_str: String) -> PathBuf {
+        let path = PathBuf::from(path_str);
+        match self.script_dir {
+            Some(ref script_dir) if path.is_relative() => script_dir.join(path),
+            _ => path,
+        }
+    }
+
+    /// File inputs are usually hidden behind a styled button or drop zone, and
+    /// smart locators only find displayed elements. So if the located element isn't
+    /// a file input itself, we look for the file input it's associated with: inside the
+    /// located element, or else inside its closest ancestor containing one. If that finds
+    /// more than one file input, we can't tell which one is meant, so it's an error.
+    async fn find_file_input(&mut self) -> RuntimeResult<WebElement> {
+        self.resolve_label().await?;
+
+        let elem = self.get_curr_elem().await?.clone();
+        let is_input = elem.tag_name().await.unwrap_or("ignore error".to_owned()) == "input";
+        let is_file_type = elem
+            .attr("type")
             .await
-            .map_err(|_| self.error("Error uploading file"))
+            .unwrap_or(None)
+            .map(|t| t == "file")
+            .unwrap_or(false);
+        if is_input && is_file_type {
+            return Ok(elem);
+        }
+
+        for xpath in [
+            ".//input[@type='file']",
+            "./ancestor::*[.//input[@type='file']][1]//input[@type='file']",
+        ] {
+            let mut file_inputs = elem
+                .find_all(By::XPath(xpath))
+                .await
+                .map_err(|e| self.driver_error(e, "Error looking for a file input"))?;
+            match file_inputs.len() {
+                0 => continue,
+                1 => return Ok(file_inputs.remove(0)),
+                n => {
+                    return Err(self.error(
+                        ErrorKind::InvalidArgument,
+                        &format!(
+                            "Found {} file inputs near the located element, so it's unclear which one to upload to. Locate the file input or its drop zone more precisely",
+                            n
+                        ),
+                    ))
+                }
+            }
+        }
+
+        Err(self.error(
+            ErrorKind::ElementNotFound,
+            "Could not find a file input to upload to",
+        ))
     }
 
-    pub async fn drag_to(&mut self, cp: CmdParam) -> RuntimeResult<(), String> {
+    /// Drags the currently located element to the element matching the provided locator.
+    /// HTML5 drag and drop only responds to drag events, which native actions don't fire in most
+    /// browsers, while libraries built on pointer events (sortable lists, kanban boards, etc.)
+    /// only respond to native actions. Either kind of drag usually "succeeds" even when the page
+    /// ignores it, so we pick a strategy based on the element, check whether the drop changed
+    /// the page, and try the other strategy if it didn't.
+    pub async fn drag_to(&mut self, cp: CmdParam) -> RuntimeResult<()> {
         let current = self.get_curr_elem().await?.clone();
         self.locate(cp, false).await?;
-        current
-            .js_drag_to(self.get_curr_elem().await?)
+        let target = self.get_curr_elem().await?.clone();
+
+        let is_html5_draggable = current
+            .attr("draggable")
+            .await
+            .unwrap_or(None)
+            .map(|draggable| draggable == "true")
+            .unwrap_or(false);
+
+        let (first_strategy, fallback_strategy) = if is_html5_draggable {
+            ("javascript", "native")
+        } else {
+            ("native", "javascript")
+        };
+
+        let first_try = self.drag_with(first_strategy, &current, &target).await;
+        if matches!(first_try, Ok(true)) {
+            self.notes_buf
+                .push(format!("Dragged using the {} strategy", first_strategy));
+            return Ok(());
+        }
+
+        let fallback = self.drag_with(fallback_strategy, &current, &target).await;
+        match (first_try, fallback) {
+            (_, Ok(true)) => {
+                self.notes_buf.push(format!(
+                    "The {} strategy had no effect, dragged using the {} strategy",
+                    first_strategy, fallback_strategy
+                ));
+                Ok(())
+            }
+            (Ok(false), _) | (_, Ok(false)) => {
+                self.notes_buf.push(
+                    "Tried both drag strategies, but could not confirm the element was dropped"
+                        .to_owned(),
+                );
+                Ok(())
+            }
+            (_, Err(e)) => Err(self.driver_error(e, "Error dragging element.")),
+        }
+    }
+
+    /// Drags an element to a target with the javascript or native strategy.
+    /// Returns whether the drop changed the page: the source element moved (to a new parent,
+    /// position among its siblings, or place on the page) or was re-rendered, or the source
+    /// or target changed (attributes, children or text). Events aren't counted, since the
+    /// javascript strategy fires them itself.
+    async fn drag_with(
+        &self,
+        strategy: &str,
+        source: &WebElement,
+        target: &WebElement,
+    ) -> WebDriverResult<bool> {
+        self.driver
+            .execute(
+                r#"
+            const [source, target] = arguments;
+            const attrs = e => Array.from(e.attributes).map(a => a.name + '=' + a.value).join(' ');
+            const rect = e => {
+                const r = e.getBoundingClientRect();
+                return [r.x + window.scrollX, r.y + window.scrollY, r.width, r.height].join(',');
+            };
+            window.__suiDrag = {
+                source,
+                target,
+                parent: source.parentElement,
+                prev: source.previousElementSibling,
+                next: source.nextElementSibling,
+                sourceRect: rect(source),
+                sourceAttrs: attrs(source),
+                targetAttrs: attrs(target),
+                targetChildren: target.childElementCount,
+                targetText: target.textContent,
+            };
+            "#,
+                vec![source.to_json()?, target.to_json()?],
+            )
+            .await?;
+
+        let res = if strategy == "javascript" {
+            source.js_drag_to(target).await
+        } else {
+            self.native_drag_to(source, target).await
+        };
+
+        // Give the page a moment to re-render after the drop
+        if res.is_ok() {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+        }
+
+        // Always compare, since it also cleans up the snapshot taken before the drag
+        let changed = self
+            .driver
+            .execute(
+                r#"
+            const before = window.__suiDrag;
+            delete window.__suiDrag;
+            if (!before) {
+                return false;
+            }
+            const attrs = e => Array.from(e.attributes).map(a => a.name + '=' + a.value).join(' ');
+            const rect = e => {
+                const r = e.getBoundingClientRect();
+                return [r.x + window.scrollX, r.y + window.scrollY, r.width, r.height].join(',');
+            };
+            const { source, target } = before;
+
+            // Frameworks often re-render a list after a drop, replacing the source element
+            if (!source.isConnected) {
+                return true;
+            }
+            const sourceChanged = source.parentElement !== before.parent
+                || source.previousElementSibling !== before.prev
+                || source.nextElementSibling !== before.next
+                || rect(source) !== before.sourceRect
+                || attrs(source) !== before.sourceAttrs;
+            const targetChanged = target.isConnected && (
+                attrs(target) !== before.targetAttrs
+                || target.childElementCount !== before.targetChildren
+                || target.textContent !== before.targetText
+            );
+            return sourceChanged || targetChanged;
+            "#,
+                vec![],
+            )
+            .await;
+
+        res?;
+        Ok(changed?.json().as_bool().unwrap_or(false))
+    }
+
+    /// Drags the currently located element by an x, y offset, for things like
+    /// sliders and resizable panels.
+    pub async fn drag_by(&mut self, x: CmdParam, y: CmdParam) -> RuntimeResult<()> {
+        let x = self.resolve_offset(x)?;
+        let y = self.resolve_offset(y)?;
+
+        self.driver
+            .action_chain()
+            .click_and_hold_element(self.get_curr_elem().await?)
+            .move_by_offset(x, y)
+            .release()
+            .perform()
+            .await
+            .map_err(|e| self.driver_error(e, "Error dragging element"))
+    }
+
+    /// Drags an element to another using a native action chain.
+    async fn native_drag_to(
+        &self,
+        source: &WebElement,
+        target: &WebElement,
+    ) -> WebDriverResult<()> {
+        self.driver
+            .action_chain()
+            .click_and_hold_element(source)
+            // Many pointer event libraries only start a drag once the
+            // mouse has moved a few pixels while held down.
+            .move_by_offset(5, 5)
+            .move_to_element_center(target)
+            .release()
+            .perform()
             .await
-            .map_err(|_| self.error("Error dragging element."))
     }
 
-    pub async fn select(&mut self, cp: CmdParam) -> RuntimeResult<(), String> {
+    pub async fn select(&mut self, cp: CmdParam) -> RuntimeResult<()> {
         let option_text = self.resolve(cp)?;
 
+        match self.native_select().await? {
+            Some(select_elm) => {
+                // Try to select the option by its exact text, then by partial text
+                if select_elm
+                    .select_by_visible_text(&option_text)
+                    .await
+                    .is_ok()
+                {
+                    return Ok(());
+                }
+                select_elm
+                    .select_by_partial_text(&option_text)
+                    .await
+                    .map_err(|e| {
+                        self.driver_error(e, &format!("Could not select text {}", option_text))
+                    })
+            }
+            None => self.select_custom_option(&option_text).await,
+        }
+    }
+
+    /// Selects an option of a native select element by its value attribute.
+    pub async fn select_value(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let value = self.resolve(cp)?;
+
+        self.native_select()
+            .await?
+            .ok_or(self.error(
+                ErrorKind::NotInteractable,
+                "Element is not a <select> element",
+            ))?
+            .select_by_value(&value)
+            .await
+            .map_err(|e| self.driver_error(e, &format!("Could not select value {}", value)))
+    }
+
+    /// Selects an option of a native select element by its (zero based) index.
+    pub async fn select_index(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let index_str = self.resolve(cp)?;
+        let index = index_str.trim().parse::<usize>().map_err(|_| {
+            self.error(
+                ErrorKind::InvalidArgument,
+                &format!("Could not parse {} as an index", index_str),
+            )
+        })?;
+
+        self.native_select()
+            .await?
+            .ok_or(self.error(
+                ErrorKind::NotInteractable,
+                "Element is not a <select> element",
+            ))?
+            .select_by_index(index)
+            .await
+            .map_err(|e| self.driver_error(e, &format!("Could not select index {}", index)))
+    }
+
+    /// Deselects every option of a native multi-select element.
+    pub async fn deselect_all(&mut self) -> RuntimeResult<()> {
+        self.native_select()
+            .await?
+            .ok_or(self.error(
+                ErrorKind::NotInteractable,
+                "Element is not a <select> element",
+            ))?
+            .deselect_all()
+            .await
+            .map_err(|e| self.driver_error(e, "Could not deselect options"))
+    }
+
+    /// Resolves the currently located element to a native select element, if it is one.
+    async fn native_select(&mut self) -> RuntimeResult<Option<SelectElement>> {
         self.resolve_label().await?;
 
         // Sometimes, a Select element's only visible text on the page
@@ -518,98 +1479,679 @@ impl Interpreter {
             let parent_select = self
                 .get_curr_elem()
                 .await?
-                .query(By::XPath("./.."))
+                .query(By::XPath("./ancestor::select[1]"))
                 .first()
                 .await
-                .map_err(|_| {
-                    self.error(
+                .map_err(|e| {
+                    self.driver_error(
+                        e,
                         "Error getting parent select. Try locating the select element directly",
                     )
                 })?;
             self.set_curr_elem(parent_select, false).await?;
         }
 
-        // Try to create a select element from the current located element
-        let select_elm = SelectElement::new(self.get_curr_elem().await?)
+        if self
+            .get_curr_elem()
+            .await?
+            .tag_name()
             .await
-            .map_err(|_| self.error("Element is not a <select> element"))?;
+            .unwrap_or("ignore error".to_owned())
+            != "select"
+        {
+            return Ok(None);
+        }
 
-        // Try to select the element by text
-        select_elm
-            .select_by_visible_text(&option_text)
+        // Try to create a select element from the current located element
+        SelectElement::new(self.get_curr_elem().await?)
             .await
-            .map_err(|_| self.error(&format!("Could not select text {}", option_text)))
+            .map(Some)
+            .map_err(|e| self.driver_error(e, "Element is not a <select> element"))
     }
 
-    pub async fn chill(&mut self, cp: CmdParam) -> RuntimeResult<(), String> {
-        let time_to_wait = match self.resolve(cp)?.parse::<u64>() {
-            Ok(time) => time,
-            _ => return Err(self.error("Could not parse time to wait as integer.")),
-        };
-
-        tokio::time::sleep(tokio::time::Duration::from_secs(time_to_wait)).await;
+    /// Most component libraries don't use native select elements, but build
+    /// dropdowns out of ARIA listboxes and comboboxes instead. Opens the dropdown
+    /// (if it isn't open already) and clicks the option matching the provided text.
+    async fn select_custom_option(&mut self, option_text: &str) -> RuntimeResult<()> {
+        let role = self
+            .get_curr_elem()
+            .await?
+            .attr("role")
+            .await
+            .map_err(|e| self.driver_error(e, "Error reading role attribute"))?;
+        let expanded = self
+            .get_curr_elem()
+            .await?
+            .attr("aria-expanded")
+            .await
+            .map_err(|e| self.driver_error(e, "Error reading aria-expanded attribute"))?;
 
-        Ok(())
+        // A listbox is already showing its options, and an expanded combobox is already open
+        if role.as_deref() != Some("listbox") && expanded.as_deref() != Some("true") {
+            self.click().await?;
+        }
+
+        let option_literal = xpath_literal(option_text);
+        let option = self
+            .driver
+            .query(By::XPath(&format!(
+                "//*[@role='option'][normalize-space(.)={}]",
+                option_literal
+            )))
+            .or(By::XPath(&format!(
+                "//*[@role='option'][contains(., {})]",
+                option_literal
+            )))
+            .and_displayed()
+            .first()
+            .await
+            .map_err(|e| self.driver_error(e, &format!("Could not find option {}", option_text)))?;
+
+        // Click the option, but keep the dropdown in focus for the commands after this one
+        let dropdown = self.curr_elem.clone();
+        let locator = self.locator.clone();
+        self.set_curr_elem(option, false).await?;
+        let res = self.click().await;
+        self.curr_elem = dropdown;
+        self.locator = locator;
+        res
     }
 
-    pub async fn press(&mut self, cp: CmdParam) -> RuntimeResult<(), String> {
-        let key_to_press = match self.resolve(cp)?.as_ref() {
-            "Enter" => Key::Enter,
-            _ => return Err(self.error("Unsupported Key")),
+    pub async fn chill(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let time_to_wait = match self.resolve(cp)?.parse::<u64>() {
+            Ok(time) => time,
+            _ => {
+                return Err(self.error(
+                    ErrorKind::InvalidArgument,
+                    "Could not parse time to wait as integer.",
+                ))
+            }
         };
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(time_to_wait)).await;
+
+        Ok(())
+    }
+
+    pub async fn press(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let key_name = self.resolve(cp)?;
+        let key_to_press = key_from_name(&key_name).ok_or(self.error(
+            ErrorKind::InvalidArgument,
+            &format!("Unsupported Key: {}", key_name),
+        ))?;
         self.get_curr_elem()
             .await?
             .send_keys("" + &key_to_press)
             .await
-            .map_err(|_| {
-                self.error("Error pressing key. Make sure you have an element in focus first.")
+            .map_err(|e| {
+                self.driver_error(
+                    e,
+                    "Error pressing key. Make sure you have an element in focus first.",
+                )
             })
     }
 
     /// Reads the text of the currently located element to a variable.
-    pub async fn read_to(&mut self, name: String) -> RuntimeResult<(), String> {
+    pub async fn read_to(&mut self, name: String) -> RuntimeResult<()> {
         let txt = self
             .get_curr_elem()
             .await?
             .text()
             .await
-            .map_err(|_| self.error("Error getting text from element"))?;
+            .map_err(|e| self.driver_error(e, "Error getting text from element"))?;
         self.environment.set_variable(name, txt);
         Ok(())
     }
 
-    /// Re-executes the commands since the last catch-error stmt.
-    pub fn try_again(&mut self) {
-        self.tried_again = true;
+    /// Reads the text of the currently open alert to a variable.
+    pub async fn read_alert_to(&mut self, name: String) -> RuntimeResult<()> {
+        let txt = self.driver.get_alert_text().await.map_err(|e| {
+            self.driver_error(e, "Error getting alert text. Is there an alert open?")
+        })?;
+        self.environment.set_variable(name, txt);
+        Ok(())
+    }
+
+    /// Types text into the currently open prompt dialog.
+    pub async fn type_into_alert(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let txt = self.resolve(cp)?;
+        self.driver
+            .send_alert_text(txt)
+            .await
+            .map_err(|e| self.driver_error(e, "Error typing into alert. Is there a prompt open?"))
+    }
+
+    /// Errors if the text of the currently open alert doesn't match the provided text.
+    pub async fn assert_alert_text(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let expected = self.resolve(cp)?;
+        // Having no alert to check is a failed assertion too
+        let actual = self
+            .driver
+            .get_alert_text()
+            .await
+            .map_err(|e| RuntimeError {
+                kind: ErrorKind::AssertionFailed,
+                ..self.driver_error(e, "Error getting alert text. Is there an alert open?")
+            })?;
+
+        if actual != expected {
+            return Err(self.error(
+                ErrorKind::AssertionFailed,
+                &format!(
+                    "Expected alert text \"{}\" but found \"{}\"",
+                    expected, actual
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    /// If the script has an alert policy and an alert is open, accepts or dismisses it
+    /// so it doesn't cause every following command to fail.
+    async fn handle_unexpected_alert(&mut self) {
+        if self.alert_policy == AlertPolicy::Ignore {
+            return;
+        }
+
+        // Getting the alert text fails when there's no alert open
+        if let Ok(txt) = self.driver.get_alert_text().await {
+            let handled = match self.alert_policy {
+                AlertPolicy::Accept => self.driver.accept_alert().await,
+                AlertPolicy::Dismiss => self.driver.dismiss_alert().await,
+                AlertPolicy::Ignore => return,
+            };
+            if handled.is_ok() {
+                self.notes_buf.push(format!(
+                    "Handled unexpected alert \"{}\" ({})",
+                    txt, self.alert_policy
+                ));
+            }
+        }
+    }
+
+    /// Adds a cookie for the current domain.
+    pub async fn set_cookie(&mut self, name: CmdParam, value: CmdParam) -> RuntimeResult<()> {
+        let name = self.resolve(name)?;
+        let value = self.resolve(value)?;
+        self.driver
+            .add_cookie(Cookie::new(name, value))
+            .await
+            .map_err(|e| self.driver_error(e, "Error setting cookie"))
+    }
+
+    /// Deletes all the cookies for the current domain.
+    pub async fn delete_cookies(&mut self) -> RuntimeResult<()> {
+        self.driver
+            .delete_all_cookies()
+            .await
+            .map_err(|e| self.driver_error(e, "Error deleting cookies"))
+    }
+
+    /// Reads the value of a cookie to a variable.
+    pub async fn read_cookie(&mut self, name: CmdParam, var: String) -> RuntimeResult<()> {
+        let name = self.resolve(name)?;
+        let cookie = self
+            .driver
+            .get_named_cookie(&name)
+            .await
+            .map_err(|e| self.driver_error(e, &format!("Could not find cookie {}", name)))?;
+        self.environment
+            .set_variable(var, cookie.value().to_owned());
+        Ok(())
+    }
+
+    /// Sets an item in local or session storage.
+    /// The storage argument is the name of the storage object on the window.
+    async fn set_storage_item(
+        &mut self,
+        storage: &str,
+        key: CmdParam,
+        value: CmdParam,
+    ) -> RuntimeResult<()> {
+        let key = self.resolve(key)?;
+        let value = self.resolve(value)?;
+        self.driver
+            .execute(
+                &format!("window.{}.setItem(arguments[0], arguments[1]);", storage),
+                vec![
+                    serde_json::Value::String(key),
+                    serde_json::Value::String(value),
+                ],
+            )
+            .await
+            .map_err(|e| self.driver_error(e, &format!("Error setting {} item", storage)))?;
+        Ok(())
+    }
+
+    /// Reads an item from local or session storage to a variable.
+    /// The storage argument is the name of the storage object on the window.
+    async fn read_storage_item(
+        &mut self,
+        storage: &str,
+        key: CmdParam,
+        var: String,
+    ) -> RuntimeResult<()> {
+        let key = self.resolve(key)?;
+        let ret = self
+            .driver
+            .execute(
+                &format!("return window.{}.getItem(arguments[0]);", storage),
+                vec![serde_json::Value::String(key.clone())],
+            )
+            .await
+            .map_err(|e| self.driver_error(e, &format!("Error reading {} item", storage)))?;
+        let value = ret
+            .json()
+            .as_str()
+            .ok_or(self.error(
+                ErrorKind::InvalidArgument,
+                &format!("Could not find {} item {}", storage, key),
+            ))?
+            .to_owned();
+        self.environment.set_variable(var, value);
+        Ok(())
+    }
+
+    /// Saves the cookies, local storage and session storage of the current site to a json file,
+    /// so another script can restore them with load-session.
+    pub async fn save_session(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let path = self.script_relative_path(self.resolve(cp)?);
+
+        let url = self
+            .driver
+            .current_url()
+            .await
+            .map_err(|e| self.driver_error(e, "Error reading current url"))?
+            .to_string();
+        let cookies = self
+            .driver
+            .get_all_cookies()
+            .await
+            .map_err(|e| self.driver_error(e, "Error reading cookies"))?
+            .iter()
+            .map(SavedCookie::from)
+            .collect();
+        let storage = self
+            .driver
+            .execute(
+                r#"
+            return {
+                local: Object.assign({}, window.localStorage),
+                session: Object.assign({}, window.sessionStorage)
+            };
+            "#,
+                vec![],
+            )
+            .await
+            .map_err(|e| self.driver_error(e, "Error reading web storage"))?;
+        let local_storage = serde_json::from_value(storage.json()["local"].clone())
+            .map_err(|_| self.error(ErrorKind::Other, "Error reading local storage"))?;
+        let session_storage = serde_json::from_value(storage.json()["session"].clone())
+            .map_err(|_| self.error(ErrorKind::Other, "Error reading session storage"))?;
+
+        let session = SavedSession {
+            url,
+            cookies,
+            local_storage,
+            session_storage,
+        };
+        let json = serde_json::to_string_pretty(&session)
+            .map_err(|_| self.error(ErrorKind::Other, "Error serializing session"))?;
+        std::fs::write(&path, json).map_err(|_| {
+            self.error(
+                ErrorKind::Io,
+                &format!("Error writing session to {}", path.display()),
+            )
+        })
+    }
+
+    /// Restores a session saved by save-session. Navigates to the page the session was saved on,
+    /// restores the cookies and web storage, and then loads the page again with the restored state.
+    /// Errors if the file doesn't exist, so scripts can fall back to logging in normally.
+    pub async fn load_session(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let path = self.resolve_path(self.resolve(cp)?);
+        let json = std::fs::read_to_string(&path).map_err(|_| {
+            self.error(
+                ErrorKind::Io,
+                &format!("Could not read session file {}", path.display()),
+            )
+        })?;
+        let session: SavedSession = serde_json::from_str(&json).map_err(|_| {
+            self.error(
+                ErrorKind::InvalidArgument,
+                &format!("Could not parse session file {}", path.display()),
+            )
+        })?;
+
+        // Cookies and storage can only be set for the site the browser is currently on
+        self.driver
+            .goto(&session.url)
+            .await
+            .map_err(|e| self.driver_error(e, "Error navigating to saved session url"))?;
+
+        for cookie in session.cookies.into_iter() {
+            self.driver
+                .add_cookie(cookie.into())
+                .await
+                .map_err(|e| self.driver_error(e, "Error restoring cookie"))?;
+        }
+
+        self.driver
+            .execute(
+                r#"
+            for (const [key, value] of Object.entries(arguments[0])) {
+                window.localStorage.setItem(key, value);
+            }
+            for (const [key, value] of Object.entries(arguments[1])) {
+                window.sessionStorage.setItem(key, value);
+            }
+            "#,
+                vec![
+                    serde_json::to_value(&session.local_storage).map_err(|_| {
+                        self.error(ErrorKind::Other, "Error serializing local storage")
+                    })?,
+                    serde_json::to_value(&session.session_storage).map_err(|_| {
+                        self.error(ErrorKind::Other, "Error serializing session storage")
+                    })?,
+                ],
+            )
+            .await
+            .map_err(|e| self.driver_error(e, "Error restoring web storage"))?;
+
+        self.driver
+            .goto(&session.url)
+            .await
+            .map_err(|e| self.driver_error(e, "Error navigating to saved session url"))
+    }
+
+    /// Runs some javascript in the browser, passing the currently located element (if there is one)
+    /// as arguments[0]. If a variable name is provided, the value returned by the script is saved to it.
+    pub async fn execute_js(&mut self, cp: CmdParam, var: Option<String>) -> RuntimeResult<()> {
+        let script = self.resolve(cp)?;
+
+        let mut args = vec![];
+        if self.curr_elem.is_some() {
+            args.push(
+                self.get_curr_elem()
+                    .await?
+                    .to_json()
+                    .map_err(|e| self.driver_error(e, "Error jsonifying element"))?,
+            );
+        }
+
+        let ret = self
+            .driver
+            .execute(&script, args)
+            .await
+            .map_err(|e| self.driver_error(e, "Error executing javascript"))?;
+
+        if let Some(var) = var {
+            let value = match ret.json() {
+                serde_json::Value::String(s) => s.clone(),
+                serde_json::Value::Null => String::new(),
+                other => other.to_string(),
+            };
+            self.environment.set_variable(var, value);
+        }
+
+        Ok(())
+    }
+
+    /// Scrolls to the bottom of the page. When used with under, scrolls to the
+    /// bottom of the under element instead (for scrollable containers like feeds).
+    pub async fn scroll_to_bottom(&mut self) -> RuntimeResult<()> {
+        let res = match self.under_element {
+            Some(ref container) => {
+                self.driver
+                    .execute(
+                        "arguments[0].scrollTop = arguments[0].scrollHeight;",
+                        vec![container
+                            .to_json()
+                            .map_err(|e| self.driver_error(e, "Error jsonifying element"))?],
+                    )
+                    .await
+            }
+            None => {
+                self.driver
+                    .execute(
+                        "window.scrollTo(0, document.documentElement.scrollHeight);",
+                        vec![],
+                    )
+                    .await
+            }
+        };
+        res.map(|_| ())
+            .map_err(|e| self.driver_error(e, "Error scrolling to bottom"))
+    }
+
+    /// Scrolls the page by an x, y offset. When used with under, scrolls
+    /// the under element instead.
+    pub async fn scroll_by(&mut self, x: CmdParam, y: CmdParam) -> RuntimeResult<()> {
+        let x = self.resolve_offset(x)?;
+        let y = self.resolve_offset(y)?;
+
+        let res = match self.under_element {
+            Some(ref container) => {
+                self.driver
+                    .execute(
+                        "arguments[0].scrollBy(arguments[1], arguments[2]);",
+                        vec![
+                            container
+                                .to_json()
+                                .map_err(|e| self.driver_error(e, "Error jsonifying element"))?,
+                            x.into(),
+                            y.into(),
+                        ],
+                    )
+                    .await
+            }
+            None => {
+                self.driver
+                    .execute(
+                        "window.scrollBy(arguments[0], arguments[1]);",
+                        vec![x.into(), y.into()],
+                    )
+                    .await
+            }
+        };
+        res.map(|_| ())
+            .map_err(|e| self.driver_error(e, "Error scrolling"))
+    }
+
+    /// Scrolls the currently located element into view with the provided alignment
+    /// (top, center or bottom). Useful for keeping elements out from under sticky headers.
+    pub async fn scroll_into_view(&mut self, cp: Option<CmdParam>) -> RuntimeResult<()> {
+        let alignment = match cp {
+            Some(cp) => self.resolve(cp)?,
+            None => "center".to_owned(),
+        };
+        let block = match alignment.as_str() {
+            "top" => "start",
+            "center" => "center",
+            "bottom" => "end",
+            _ => {
+                return Err(self.error(
+                    ErrorKind::InvalidArgument,
+                    &format!(
+                        "Unsupported alignment {}. Use top, center or bottom",
+                        alignment
+                    ),
+                ))
+            }
+        };
+
+        let elem = self
+            .get_curr_elem()
+            .await?
+            .to_json()
+            .map_err(|e| self.driver_error(e, "Error jsonifying element"))?;
+        self.driver
+            .execute(
+                "arguments[0].scrollIntoView({block: arguments[1], inline: 'nearest'});",
+                vec![elem, block.into()],
+            )
+            .await
+            .map(|_| ())
+            .map_err(|e| self.driver_error(e, "Error scrolling element into view"))
+    }
+
+    /// Resizes the browser window, for testing layouts at different breakpoints.
+    pub async fn set_window_size(
+        &mut self,
+        width: CmdParam,
+        height: CmdParam,
+    ) -> RuntimeResult<()> {
+        let width_str = self.resolve(width)?;
+        let height_str = self.resolve(height)?;
+        let width = width_str.trim().parse().map_err(|_| {
+            self.error(
+                ErrorKind::InvalidArgument,
+                &format!("Could not parse {} as a width", width_str),
+            )
+        })?;
+        let height = height_str.trim().parse().map_err(|_| {
+            self.error(
+                ErrorKind::InvalidArgument,
+                &format!("Could not parse {} as a height", height_str),
+            )
+        })?;
+
+        self.driver
+            .set_window_rect(0, 0, width, height)
+            .await
+            .map_err(|e| self.driver_error(e, "Error resizing window"))
+    }
+
+    /// Re-executes the commands since the last catch-error stmt. Each attempt is nested in the report,
+    /// and an error during the last attempt ends the script.
+    pub async fn try_again(
+        &mut self,
+        attempts: Option<CmdParam>,
+        delay: Option<CmdParam>,
+    ) -> RuntimeResult<()> {
+        let max_attempts = match attempts {
+            Some(cp) => {
+                let attempts = self.resolve(cp)?;
+                match attempts.parse::<usize>() {
+                    Ok(n) if n > 0 => n,
+                    _ => {
+                        return Err(self.error(
+                            ErrorKind::InvalidArgument,
+                            &format!("Could not parse {} as a number of attempts", attempts),
+                        ))
+                    }
+                }
+            }
+            None => 1,
+        };
+        let delay = match delay {
+            Some(cp) => {
+                let delay = self.resolve(cp)?;
+                delay.parse::<u64>().map_err(|_| {
+                    self.error(
+                        ErrorKind::InvalidArgument,
+                        &format!("Could not parse {} as a number of seconds", delay),
+                    )
+                })?
+            }
+            None => 0,
+        };
+
+        let attempt = self.retry.as_ref().map(|r| r.attempt + 1).unwrap_or(1);
+        self.retry = Some(Retry {
+            attempt,
+            max_attempts,
+        });
+        self.pending_attempt = Some(format!("try-again attempt {} of {}", attempt, max_attempts));
+
+        tokio::time::sleep(tokio::time::Duration::from_secs(delay)).await;
+
+        // The stmts are popped off the end, so push them in reverse order: the catch-error stmt
+        // (so a failed attempt can be handled), the marker for a successful attempt, and then the stmts themselves.
+        if let Some(cs) = self.catch_stmt.clone() {
+            self.stmts.push(Stmt::CatchErr(cs));
+            self.replayed_catch = true;
+        }
         self.stmts.push(Stmt::SetTryAgainFieldToFalse);
+        let replay = std::mem::take(&mut self.stmts_since_last_error_handling);
+        self.stmts.extend(replay.into_iter().rev());
+        Ok(())
+    }
 
-        // This would be more efficient with some kind of mem_swap type function.
-        self.stmts
-            .append(&mut self.stmts_since_last_error_handling.clone());
-        self.stmts_since_last_error_handling.clear();
+    /// Ends the current attempt of try-again, going back to normal execution.
+    fn end_attempt(&mut self) {
+        if self.retry.take().is_some() {
+            if let Some(ref mut reporter) = self.reporter {
+                reporter.end_group();
+            }
+        }
     }
 
     /// Takes a screenshot of the page.
-    pub async fn screenshot(&mut self) -> RuntimeResult<(), String> {
+    pub async fn screenshot(&mut self) -> RuntimeResult<()> {
         let ss = self
             .driver
             .screenshot_as_png()
             .await
-            .map_err(|_| self.error("Error taking screenshot."))?;
+            .map_err(|e| self.driver_error(e, "Error taking screenshot."))?;
         self.screenshot_buf.push(ss);
         Ok(())
     }
 
     /// Refreshes the webpage
-    pub async fn refresh(&mut self) -> RuntimeResult<(), String> {
+    pub async fn refresh(&mut self) -> RuntimeResult<()> {
         self.driver
             .refresh()
             .await
-            .map_err(|_| self.error("Error refreshing page"))
+            .map_err(|e| self.driver_error(e, "Error refreshing page"))
+    }
+
+    /// Navigates back in the browser history
+    pub async fn back(&mut self) -> RuntimeResult<()> {
+        self.driver
+            .back()
+            .await
+            .map_err(|e| self.driver_error(e, "Error navigating back"))
+    }
+
+    /// Navigates forward in the browser history
+    pub async fn forward(&mut self) -> RuntimeResult<()> {
+        self.driver
+            .forward()
+            .await
+            .map_err(|e| self.driver_error(e, "Error navigating forward"))
+    }
+
+    /// Waits (up to 30 seconds) for the current url to contain the provided text.
+    /// Useful for flows which redirect several times, like SSO logins.
+    pub async fn wait_for_url(&mut self, cp: CmdParam) -> RuntimeResult<()> {
+        let pattern = self.resolve(cp)?;
+
+        let mut current_url = String::new();
+        for _ in 0..60 {
+            current_url = self
+                .driver
+                .current_url()
+                .await
+                .map_err(|e| self.driver_error(e, "Error reading current url"))?
+                .to_string();
+            if current_url.contains(&pattern) {
+                return Ok(());
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        }
+
+        Err(self.error(
+            ErrorKind::Timeout,
+            &format!(
+                "Timed out waiting for url to contain {}. Current url is {}",
+                pattern, current_url
+            ),
+        ))
     }
 
     /// Tries to click on the currently located web element.
-    pub async fn click(&mut self) -> RuntimeResult<(), String> {
+    pub async fn click(&mut self) -> RuntimeResult<()> {
         self.resolve_label().await?;
 
         // We need to wait for the element to be clickable by default,
@@ -624,33 +2166,188 @@ impl Interpreter {
             .click()
             .perform()
             .await
-            .map_err(|e| self.error(&format!("Error clicking element: {}", e)))
+            .map_err(|e| self.driver_error(e, "Error clicking element"))
     }
 
-    /// Tries to type into the current element
-    pub async fn type_into_elem(&mut self, cmd_param: CmdParam) -> RuntimeResult<(), String> {
-        let txt = self.resolve(cmd_param)?;
+    /// Moves the mouse over the currently located element, for things like
+    /// menus which only open on hover.
+    pub async fn hover(&mut self) -> RuntimeResult<()> {
+        self.resolve_label().await?;
 
+        self.driver
+            .action_chain()
+            .move_to_element_center(self.get_curr_elem().await?)
+            .perform()
+            .await
+            .map_err(|e| self.driver_error(e, "Error hovering over element"))
+    }
+
+    /// Tries to double click on the currently located web element.
+    pub async fn double_click(&mut self) -> RuntimeResult<()> {
         self.resolve_label().await?;
 
-        // Instead of typing into the located element,
-        // we'll click the located element, then type
-        // into the "active" element. This will a help
-        // a lot with custom popup typing interactions.
+        // Same as click, wait for the element to be clickable but ignore the error
+        let _ = self.get_curr_elem().await?.wait_until().clickable().await;
+
+        self.driver
+            .action_chain()
+            .move_to_element_center(self.get_curr_elem().await?)
+            .double_click()
+            .perform()
+            .await
+            .map_err(|e| self.driver_error(e, "Error double clicking element"))
+    }
 
-        // Click the current element
-        self.click().await?;
+    /// Tries to right click on the currently located web element.
+    pub async fn right_click(&mut self) -> RuntimeResult<()> {
+        self.resolve_label().await?;
 
-        // Wait a second in case some javascript needs to happen
-        // for fancy components
-        std::thread::sleep(std::time::Duration::from_secs(1));
+        // Same as click, wait for the element to be clickable but ignore the error
+        let _ = self.get_curr_elem().await?.wait_until().clickable().await;
 
-        // Get the active element
-        let active_elm = self
+        self.driver
+            .action_chain()
+            .move_to_element_center(self.get_curr_elem().await?)
+            .context_click()
+            .perform()
+            .await
+            .map_err(|e| self.driver_error(e, "Error right clicking element"))
+    }
+
+    /// Clicks at an offset. If an element is located, the offset is from the center
+    /// of that element. Otherwise, the offset is from the top left corner of the viewport.
+    /// Useful for canvas based components like charts.
+    pub async fn click_at(&mut self, x: CmdParam, y: CmdParam) -> RuntimeResult<()> {
+        let x = self.resolve_offset(x)?;
+        let y = self.resolve_offset(y)?;
+
+        let action_chain = if self.curr_elem.is_some() {
+            self.driver.action_chain().move_to_element_with_offset(
+                self.get_curr_elem().await?,
+                x,
+                y,
+            )
+        } else {
+            self.driver.action_chain().move_to(x, y)
+        };
+
+        action_chain
+            .click()
+            .perform()
+            .await
+            .map_err(|e| self.driver_error(e, &format!("Error clicking at offset ({}, {})", x, y)))
+    }
+
+    /// Resolves a cmd_param to a pixel offset.
+    fn resolve_offset(&self, cp: CmdParam) -> RuntimeResult<i64> {
+        let offset = self.resolve(cp)?;
+        offset.trim().parse::<i64>().map_err(|_| {
+            self.error(
+                ErrorKind::InvalidArgument,
+                &format!("Could not parse {} as a pixel offset", offset),
+            )
+        })
+    }
+
+    /// Reads whether the currently located element is checked. Switches are usually
+    /// located by their visible text, so the state is read from the control that text belongs to:
+    /// the element itself, the control of a label, a control inside the element, or a control
+    /// the element is inside of. Custom switches report their state through aria-checked,
+    /// so that takes precedence over the native checked state.
+    async fn is_checked(&mut self) -> RuntimeResult<bool> {
+        let elem = self.get_curr_elem().await?.clone();
+        let ret = self
             .driver
-            .active_element()
+            .execute(
+                r#"
+            const elem = arguments[0];
+            const isToggle = e => e.hasAttribute('aria-checked')
+                || ['switch', 'checkbox', 'radio', 'menuitemcheckbox'].includes(e.getAttribute('role'))
+                || (e.tagName === 'INPUT' && ['checkbox', 'radio'].includes(e.type));
+
+            if (isToggle(elem)) {
+                return elem;
+            }
+            if (elem.tagName === 'LABEL' && elem.control && isToggle(elem.control)) {
+                return elem.control;
+            }
+            const inner = elem.querySelector(
+                '[aria-checked], [role=switch], [role=checkbox], [role=radio], input[type=checkbox], input[type=radio]'
+            );
+            if (inner) {
+                return inner;
+            }
+            for (let parent = elem.parentElement; parent; parent = parent.parentElement) {
+                if (isToggle(parent)) {
+                    return parent;
+                }
+            }
+            if (elem.id) {
+                const labelled = document.querySelector(`[aria-labelledby~="${CSS.escape(elem.id)}"]`);
+                if (labelled && isToggle(labelled)) {
+                    return labelled;
+                }
+            }
+            return null;
+            "#,
+                vec![elem
+                    .to_json()
+                    .map_err(|e| self.driver_error(e, "Error jsonifying element"))?],
+            )
+            .await
+            .map_err(|e| self.driver_error(e, "Error finding the checkbox for element"))?;
+
+        // Clicking without knowing the state could turn off something that was already on
+        if ret.json().is_null() {
+            return Err(self.error(
+                ErrorKind::NotInteractable,
+                "Could not find a checkbox, radio button or switch for the located element",
+            ));
+        }
+        let toggle = ret
+            .element()
+            .map_err(|e| self.driver_error(e, "Error finding the checkbox for element"))?;
+
+        let aria_checked = toggle
+            .attr("aria-checked")
             .await
-            .map_err(|_| self.error("Could not locate active element"))?;
+            .map_err(|e| self.driver_error(e, "Error reading aria-checked attribute"))?;
+
+        match aria_checked {
+            Some(state) => Ok(state == "true"),
+            None => toggle
+                .is_selected()
+                .await
+                .map_err(|e| self.driver_error(e, "Error reading checked state of element")),
+        }
+    }
+
+    /// Clicks the currently located checkbox, radio button, or switch only if
+    /// it is not already in the desired state.
+    pub async fn set_checked(&mut self, checked: bool) -> RuntimeResult<()> {
+        self.resolve_label().await?;
+
+        if self.is_checked().await? != checked {
+            self.click().await?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the text to type. Key tokens like {Enter} are only expanded in text written
+    /// in the script, so variables (like passwords) are always typed exactly as they are.
+    fn resolve_typed_text(&self, cmd_param: CmdParam) -> RuntimeResult<String> {
+        match cmd_param {
+            CmdParam::String(s) => Ok(expand_key_tokens(&s)),
+            CmdParam::Variable(_) => self.resolve(cmd_param),
+        }
+    }
+
+    /// Tries to type into the current element
+    pub async fn type_into_elem(&mut self, cmd_param: CmdParam) -> RuntimeResult<()> {
+        let txt = self.resolve_typed_text(cmd_param)?;
+
+        let active_elm = self.focus_for_typing().await?;
 
         // Clear the element, but don't error if that fails
         let _ = active_elm.clear().await;
@@ -659,16 +2356,128 @@ impl Interpreter {
         active_elm
             .send_keys(txt)
             .await
-            .map_err(|_| self.error("Error typing into element"))
+            .map_err(|e| self.driver_error(e, "Error typing into element"))
+    }
+
+    /// Tries to type into the current element without clearing it first.
+    pub async fn append(&mut self, cmd_param: CmdParam) -> RuntimeResult<()> {
+        let txt = self.resolve_typed_text(cmd_param)?;
+
+        let active_elm = self.focus_for_typing().await?;
+
+        active_elm
+            .send_keys(txt)
+            .await
+            .map_err(|e| self.driver_error(e, "Error typing into element"))
+    }
+
+    /// Tries to type into the current element one character at a time, for components
+    /// like autocompletes which react to individual keystrokes.
+    pub async fn type_slowly(&mut self, cmd_param: CmdParam) -> RuntimeResult<()> {
+        let txt = self.resolve_typed_text(cmd_param)?;
+
+        let active_elm = self.focus_for_typing().await?;
+
+        // Clear the element, but don't error if that fails
+        let _ = active_elm.clear().await;
+
+        for c in txt.chars() {
+            active_elm
+                .send_keys(c.to_string())
+                .await
+                .map_err(|e| self.driver_error(e, "Error typing into element"))?;
+
+            // Roughly the cadence of a fast human typist
+            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        }
+
+        Ok(())
+    }
+
+    /// Tries to empty the current element.
+    pub async fn clear(&mut self) -> RuntimeResult<()> {
+        let active_elm = self.focus_for_typing().await?;
+
+        // Some masked inputs reject a regular clear, or immediately re-render their value.
+        // If the field still isn't empty, fall back to clearing it with javascript.
+        let cleared = active_elm.clear().await.is_ok()
+            && active_elm
+                .prop("value")
+                .await
+                .map(|value| value.unwrap_or_default().is_empty())
+                .unwrap_or(false);
+
+        if !cleared {
+            // Select-all shortcuts differ between platforms, so the value is emptied directly.
+            // The native setter is used so frameworks like React notice the change.
+            self.driver
+                .execute(
+                    r#"
+            const elem = arguments[0];
+            if ('value' in elem) {
+                const proto = Object.getPrototypeOf(elem);
+                const setter = Object.getOwnPropertyDescriptor(proto, 'value')?.set;
+                setter ? setter.call(elem, '') : elem.value = '';
+            } else {
+                elem.textContent = '';
+            }
+            elem.dispatchEvent(new Event('input', { bubbles: true }));
+            elem.dispatchEvent(new Event('change', { bubbles: true }));
+            "#,
+                    vec![active_elm
+                        .to_json()
+                        .map_err(|e| self.driver_error(e, "Error jsonifying element"))?],
+                )
+                .await
+                .map_err(|e| self.driver_error(e, "Error clearing element"))?;
+        }
+
+        Ok(())
     }
 
-    /// Navigates to the provided url.
-    pub async fn url_cmd(&mut self, url: CmdParam) -> RuntimeResult<(), String> {
+    /// Instead of typing into the located element,
+    /// we'll click the located element, then type
+    /// into the "active" element. This will a help
+    /// a lot with custom popup typing interactions.
+    /// Returns the active element to type into.
+    async fn focus_for_typing(&mut self) -> RuntimeResult<WebElement> {
+        self.resolve_label().await?;
+
+        // Click the current element
+        self.click().await?;
+
+        // Wait a second in case some javascript needs to happen
+        // for fancy components
+        std::thread::sleep(std::time::Duration::from_secs(1));
+
+        // Get the active element
+        self.driver
+            .active_element()
+            .await
+            .map_err(|e| self.driver_error(e, "Could not locate active element"))
+    }
+
+    /// Navigates to the provided url. Slugs (anything that isn't a complete url)
+    /// are resolved against the base url if there is one.
+    pub async fn url_cmd(&mut self, url: CmdParam) -> RuntimeResult<()> {
         let url = self.resolve(url)?;
+        let is_complete_url = url.contains("://")
+            || url.starts_with("about:")
+            || url.starts_with("data:")
+            || url.starts_with("file:");
+        let url = match self.base_url {
+            Some(ref base_url) if !is_complete_url => format!(
+                "{}/{}",
+                base_url.trim_end_matches('/'),
+                url.trim_start_matches('/')
+            ),
+            _ => url,
+        };
+
         self.driver
             .goto(url)
             .await
-            .map_err(|_| self.error("Error navigating to page."))
+            .map_err(|e| self.driver_error(e, "Error navigating to page."))
     }
 
     /// Attempt to locate an element on the page, testing the locator in the following precedence
@@ -678,7 +2487,7 @@ impl Interpreter {
         &mut self,
         locator: CmdParam,
         scroll_into_view: bool,
-    ) -> RuntimeResult<WebElement, String> {
+    ) -> RuntimeResult<WebElement> {
         let locator = self.resolve(locator)?;
 
         // Store the locator in case we need to re-execute locate command (stale element, etc.)
@@ -806,145 +2615,267 @@ impl Interpreter {
                 .await;
         }
 
+        // Conditions only check for the element for a short window
+        if self.probing {
+            return self.probe(&locator, scroll_into_view).await;
+        }
+
         // Regular queries
         for wait in [0, 5, 10, 20, 30] {
-            std::thread::sleep(std::time::Duration::from_secs(wait));
+            tokio::time::sleep(tokio::time::Duration::from_secs(wait)).await;
 
-            // Locate an input element by its placeholder
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::XPath(&format!("//input[@placeholder='{}']", locator)))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
+            if let Some(found_elem) = self.find_once(&locator).await {
                 return self.set_curr_elem(found_elem, scroll_into_view).await;
             }
+        }
 
-            // Try to find the element by partial placeholder
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::XPath(&format!(
-                    "//input[contains(@placeholder, '{}')]",
-                    locator
-                )))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
-            }
+        Err(self.error(
+            ErrorKind::ElementNotFound,
+            &format!("Could not locate the element {}", locator),
+        ))
+    }
 
-            // Try to find the element by its text
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::XPath(&format!("//*[text()='{}']", locator)))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
-            }
+    /// Checks whether an element matching the locator exists, only looking for the probe window
+    /// instead of waiting like locate. If it does, the element is brought into focus.
+    pub async fn exists(&mut self, locator: CmdParam) -> RuntimeResult<()> {
+        let probing = std::mem::replace(&mut self.probing, true);
+        let res = self.locate(locator, false).await.map(|_| ());
+        self.probing = probing;
+
+        // exists is a check, so a missing element is a failed assertion
+        res.map_err(|e| match e.kind {
+            ErrorKind::ElementNotFound => RuntimeError {
+                kind: ErrorKind::AssertionFailed,
+                ..e
+            },
+            _ => e,
+        })
+    }
 
-            // Try to find the element by partial text
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::XPath(&format!("//*[contains(text(), '{}')]", locator)))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
+    /// Checks for an element matching the locator until the probe window runs out,
+    /// checking at least once.
+    async fn probe(&mut self, locator: &str, scroll_into_view: bool) -> RuntimeResult<WebElement> {
+        let deadline = tokio::time::Instant::now() + self.probe_window;
+        loop {
+            if let Some(found_elem) = self.find_once(locator).await {
                 return self.set_curr_elem(found_elem, scroll_into_view).await;
             }
-
-            // Try to find an element by it's title
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::XPath(&format!("//*[@title='{}']", locator)))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
+            if tokio::time::Instant::now() >= deadline {
+                break;
             }
+            tokio::time::sleep(tokio::time::Duration::from_millis(250)).await;
+        }
 
-            // Try to locate by aria-label
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::XPath(&format!("//*[@aria-label='{}']", locator)))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
-            }
+        Err(self.error(
+            ErrorKind::ElementNotFound,
+            &format!("Could not find the element {}", locator),
+        ))
+    }
 
-            // Try to find an element by it's id
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::Id(&locator))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
+    /// Tries each way of locating an element once, in the following precedence
+    /// (placeholder, text, title, aria-label, id, name, class, tag name, xpath).
+    /// Checks whether a displayed element matches the locator in any of the ways
+    /// find_once would find it, using a single script rather than a query for each.
+    async fn appears(&self, locator: &str) -> bool {
+        self.driver
+            .execute(
+                r#"
+            const [locator] = arguments;
+            const shown = e => e.getClientRects().length > 0;
+            const all = selector => Array.from(document.querySelectorAll(selector));
+            if (all('input[placeholder]').some(e => shown(e) && e.placeholder.includes(locator))) {
+                return true;
             }
-
-            // Try to find an element by it's name
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::Name(&locator))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
+            if (document.body) {
+                const walker = document.createTreeWalker(document.body, NodeFilter.SHOW_TEXT);
+                while (walker.nextNode()) {
+                    const parent = walker.currentNode.parentElement;
+                    if (parent && walker.currentNode.data.includes(locator) && shown(parent)) {
+                        return true;
+                    }
+                }
             }
-
-            // Try to find an element by it's class
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::ClassName(&locator))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
+            const attrs = ['title', 'aria-label', 'id', 'name'];
+            if (all('[title], [aria-label], [id], [name]')
+                .some(e => shown(e) && attrs.some(a => e.getAttribute(a) === locator))) {
+                return true;
             }
-
-            // Try to find an element by tag name
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::Tag(&locator))
-                .and_displayed()
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
+            try {
+                if (all('.' + CSS.escape(locator)).some(shown) || all(locator).some(shown)) {
+                    return true;
+                }
+            } catch (e) {}
+            try {
+                return document.evaluate(locator, document, null,
+                    XPathResult.FIRST_ORDERED_NODE_TYPE, null).singleNodeValue !== null;
+            } catch (e) {
+                return false;
             }
+            "#,
+                vec![serde_json::Value::String(locator.to_owned())],
+            )
+            .await
+            .map(|ret| ret.json().as_bool().unwrap_or(false))
+            .unwrap_or(false)
+    }
 
-            // Try to find an element by xpath
-            if let Ok(found_elem) = self
-                .driver
-                .query(By::XPath(&locator))
-                .nowait()
-                .first()
-                .await
-            {
-                return self.set_curr_elem(found_elem, scroll_into_view).await;
-            }
+    async fn find_once(&self, locator: &str) -> Option<WebElement> {
+        // Locate an input element by its placeholder
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::XPath(&format!("//input[@placeholder='{}']", locator)))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to find the element by partial placeholder
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::XPath(&format!(
+                "//input[contains(@placeholder, '{}')]",
+                locator
+            )))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to find the element by its text
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::XPath(&format!("//*[text()='{}']", locator)))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to find the element by partial text
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::XPath(&format!("//*[contains(text(), '{}')]", locator)))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
         }
 
-        Err(self.error("Could not locate the element"))
+        // Try to find an element by it's title
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::XPath(&format!("//*[@title='{}']", locator)))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to locate by aria-label
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::XPath(&format!("//*[@aria-label='{}']", locator)))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to find an element by it's id
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::Id(locator))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to find an element by it's name
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::Name(locator))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to find an element by it's class
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::ClassName(locator))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to find an element by tag name
+        if let Ok(found_elem) = self
+            .driver
+            .query(By::Tag(locator))
+            .and_displayed()
+            .nowait()
+            .first()
+            .await
+        {
+            return Some(found_elem);
+        }
+
+        // Try to find an element by xpath
+        if let Ok(found_elem) = self.driver.query(By::XPath(locator)).nowait().first().await {
+            return Some(found_elem);
+        }
+
+        None
+    }
+}
+
+/// Quotes text for use as a string literal in an XPath expression. XPath has no escape
+/// characters, so text containing both kinds of quotes is built with concat().
+fn xpath_literal(text: &str) -> String {
+    if !text.contains('\'') {
+        format!("'{}'", text)
+    } else if !text.contains('"') {
+        format!("\"{}\"", text)
+    } else {
+        let parts: Vec<String> = text.split('\'').map(|part| format!("'{}'", part)).collect();
+        format!("concat({})", parts.join(", \"'\", "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xpath_literals_quote_text() {
+        assert_eq!(xpath_literal("Canada"), "'Canada'");
+        assert_eq!(xpath_literal("Cote d'Ivoire"), "\"Cote d'Ivoire\"");
+        assert_eq!(
+            xpath_literal("The \"Men's\" size"),
+            "concat('The \"Men', \"'\", 's\" size')"
+        );
     }
 }
@@ -0,0 +1,82 @@
+use thirtyfour::Key;
+
+/// Resolves the name of a key (as written in a script) to a thirtyfour Key.
+/// Names are case insensitive.
+pub fn key_from_name(name: &str) -> Option<Key> {
+    let key = match name.to_lowercase().as_str() {
+        "enter" => Key::Enter,
+        "return" => Key::Return,
+        "tab" => Key::Tab,
+        "escape" | "esc" => Key::Escape,
+        "backspace" => Key::Backspace,
+        "delete" => Key::Delete,
+        "space" => Key::Space,
+        "up" => Key::Up,
+        "down" => Key::Down,
+        "left" => Key::Left,
+        "right" => Key::Right,
+        "home" => Key::Home,
+        "end" => Key::End,
+        "pageup" => Key::PageUp,
+        "pagedown" => Key::PageDown,
+        "insert" => Key::Insert,
+        "shift" => Key::Shift,
+        "control" | "ctrl" => Key::Control,
+        "alt" => Key::Alt,
+        "meta" => Key::Meta,
+        "command" => Key::Command,
+        "f1" => Key::F1,
+        "f2" => Key::F2,
+        "f3" => Key::F3,
+        "f4" => Key::F4,
+        "f5" => Key::F5,
+        "f6" => Key::F6,
+        "f7" => Key::F7,
+        "f8" => Key::F8,
+        "f9" => Key::F9,
+        "f10" => Key::F10,
+        "f11" => Key::F11,
+        "f12" => Key::F12,
+        _ => return None,
+    };
+    Some(key)
+}
+
+/// Replaces key tokens like `{Tab}` or `{Enter}` in some text with the
+/// corresponding key, so the whole string can be sent in a single `send_keys` call.
+/// Anything between braces which isn't a known key name is left as is.
+/// # Example
+/// ```
+/// use schnauzer_ui::keys::expand_key_tokens;
+///
+/// let typed = expand_key_tokens("john{Tab}doe{Enter}");
+/// assert_eq!(typed.chars().count(), 9);
+/// assert_eq!(expand_key_tokens("{not a key}"), "{not a key}");
+/// ```
+pub fn expand_key_tokens(txt: &str) -> String {
+    let mut expanded = String::new();
+    let mut rest = txt;
+
+    while let Some(start) = rest.find('{') {
+        expanded.push_str(&rest[..start]);
+        let after_brace = &rest[start + 1..];
+
+        match after_brace
+            .find('}')
+            .and_then(|end| key_from_name(&after_brace[..end]).map(|key| (end, key)))
+        {
+            Some((end, key)) => {
+                expanded.push(char::from(key));
+                rest = &after_brace[end + 1..];
+            }
+            None => {
+                // Not a key token, so keep the brace and move on
+                expanded.push('{');
+                rest = after_brace;
+            }
+        }
+    }
+    expanded.push_str(rest);
+
+    expanded
+}
@@ -1,8 +1,12 @@
+pub mod config;
 pub mod datatable;
 pub mod environment;
+pub mod error;
 pub mod interpreter;
+pub mod keys;
 pub mod parser;
 pub mod scanner;
+pub mod session;
 pub mod test_report;
 
 use std::{
@@ -12,7 +16,7 @@ use std::{
 };
 
 use datatable::preprocess;
-use interpreter::Interpreter;
+use interpreter::{Interpreter, InterpreterConfig};
 use parser::Parser;
 use sailfish::TemplateOnce;
 use scanner::Scanner;
@@ -67,7 +71,7 @@ pub async fn run(
     file_name: String,
     driver: WebDriver,
     dt: Option<Vec<HashMap<String, String>>>,
-    is_demo: bool,
+    config: InterpreterConfig,
 ) -> WebDriverResult<bool> {
     // Preprocess the code to replace values from datatable
     if let Some(dt) = dt {
@@ -85,7 +89,7 @@ pub async fn run(
     let mut interpreter = Interpreter::new(
         driver,
         stmts,
-        is_demo,
+        config,
         Some(Report::new(file_name.clone(), output_path.clone())),
     );
     let res = interpreter.interpret(true).await;
@@ -116,7 +120,7 @@ pub async fn run_no_log(code: String, driver: WebDriver) -> WebDriverResult<bool
     let tokens = scanner.scan();
 
     let stmts = Parser::new().parse(tokens);
-    let mut interpreter = Interpreter::new(driver, stmts, false, None);
+    let mut interpreter = Interpreter::new(driver, stmts, InterpreterConfig::default(), None);
     interpreter.interpret(true).await
 }
 
@@ -126,11 +130,18 @@ pub enum SupportedBrowser {
     Chrome,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct WebDriverConfig {
     pub port: usize,
     pub headless: bool,
     pub browser: SupportedBrowser,
+
+    /// Width and height of the browser window. When not provided, the window is maximized.
+    pub window_size: Option<(u32, u32)>,
+
+    /// Name of a Chrome mobile emulation device preset (ex. "iPhone 12 Pro").
+    /// Only supported in Chrome.
+    pub device: Option<String>,
 }
 
 impl Default for WebDriverConfig {
@@ -139,6 +150,8 @@ impl Default for WebDriverConfig {
             port: 4444,
             headless: false,
             browser: SupportedBrowser::Chrome,
+            window_size: None,
+            device: None,
         }
     }
 }
@@ -148,6 +161,8 @@ pub async fn new_driver(
         port,
         headless,
         browser,
+        window_size,
+        device,
     }: WebDriverConfig,
 ) -> WebDriverResult<WebDriver> {
     let localhost = format!("http://localhost:{}", port);
@@ -157,6 +172,10 @@ pub async fn new_driver(
             if headless {
                 caps.set_headless()?;
             }
+            if let Some((width, height)) = window_size {
+                caps.add_firefox_arg(&format!("--width={}", width))?;
+                caps.add_firefox_arg(&format!("--height={}", height))?;
+            }
             WebDriver::new(&localhost, caps).await
         }
         SupportedBrowser::Chrome => {
@@ -165,11 +184,21 @@ pub async fn new_driver(
                 caps.set_headless()?;
             }
             caps.add_arg("--disable-infobars")?;
-            caps.add_arg("start-maximized")?;
+            match window_size {
+                Some((width, height)) => {
+                    caps.add_arg(&format!("--window-size={},{}", width, height))?
+                }
+                None => caps.add_arg("start-maximized")?,
+            }
             caps.add_arg("--disable-extensions")?;
             let mut prefs = HashMap::new();
             prefs.insert("profile.default_content_setting_values.notifications", 1);
             caps.add_experimental_option("prefs", prefs)?;
+            if let Some(device) = device {
+                let mut mobile_emulation = HashMap::new();
+                mobile_emulation.insert("deviceName", device);
+                caps.add_experimental_option("mobileEmulation", mobile_emulation)?;
+            }
             WebDriver::new(&localhost, caps).await
         }
     }
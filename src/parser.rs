@@ -10,8 +10,31 @@ pub enum Stmt {
     Under(CmdParam, CmdStmt),
     UnderActiveElement(CmdStmt),
 
+    /// Runs a command statement, but an error is only recorded as a soft failure
+    /// instead of stopping the script.
+    Soft(CmdStmt),
+
+    /// Declares a handler. Between statements, if an element matching the locator is on the page,
+    /// the command statement is run against it.
+    Whenever(CmdParam, CmdStmt),
+
+    /// Sets how the script should respond to alerts it wasn't expecting.
+    OnAlert(AlertPolicy),
+
+    /// Starts a try block. An error in the block skips ahead to its on-error section.
+    Try,
+
+    /// Starts the section of a try block which only runs if the block had an error.
+    OnError,
+
+    /// Starts the section of a try block which always runs, even if the script is exiting.
+    Finally,
+
+    /// Ends a try block.
+    End,
+
     /// This statement is not meant to be parsed. It is added by the interpreter
-    /// as part of try-again logic.
+    /// as part of try-again logic, and marks the end of an attempt which didn't error.
     SetTryAgainFieldToFalse,
 }
 
@@ -26,6 +49,50 @@ impl std::fmt::Display for Stmt {
             Stmt::SetTryAgainFieldToFalse => write!(f, ""),
             Stmt::Under(cp, cs) => write!(f, "under {} {}", cp, cs),
             Stmt::UnderActiveElement(cs) => write!(f, "under-active-element {}", cs),
+            Stmt::Soft(cs) => write!(f, "soft {}", cs),
+            Stmt::Whenever(cp, cs) => write!(f, "whenever {} appears then {}", cp, cs),
+            Stmt::OnAlert(policy) => write!(f, "on-alert {}", policy),
+            Stmt::Try => write!(f, "try"),
+            Stmt::OnError => write!(f, "on-error"),
+            Stmt::Finally => write!(f, "finally"),
+            Stmt::End => write!(f, "end"),
+        }
+    }
+}
+
+impl Stmt {
+    /// The line of the script the statement came from, for statements which run commands.
+    pub fn line(&self) -> Option<usize> {
+        match self {
+            Stmt::Cmd(cs)
+            | Stmt::CatchErr(cs)
+            | Stmt::Under(_, cs)
+            | Stmt::UnderActiveElement(cs)
+            | Stmt::Soft(cs)
+            | Stmt::Whenever(_, cs) => Some(cs.line),
+            Stmt::If(is) => Some(is.then_branch.line),
+            Stmt::SetVariable(sv) => Some(sv.line),
+            _ => None,
+        }
+    }
+}
+
+/// What to do with an alert that appears when the script isn't expecting one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertPolicy {
+    Accept,
+    Dismiss,
+
+    /// Leave the alert alone (the default)
+    Ignore,
+}
+
+impl std::fmt::Display for AlertPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AlertPolicy::Accept => write!(f, "accept"),
+            AlertPolicy::Dismiss => write!(f, "dismiss"),
+            AlertPolicy::Ignore => write!(f, "ignore"),
         }
     }
 }
@@ -33,21 +100,187 @@ impl std::fmt::Display for Stmt {
 #[derive(Debug, Clone, PartialEq)]
 pub struct SetVariableStmt {
     pub variable_name: String,
-    pub value: String,
+    pub value: Expr,
+
+    /// Whether the value is masked in reports
+    pub secret: bool,
+
+    /// The line of the script the statement is on (for error reporting)
+    pub line: usize,
 }
 
 impl std::fmt::Display for SetVariableStmt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "save {} as {}", self.variable_name, self.value)
+        if self.secret {
+            write!(f, "save secret {} as {}", self.value, self.variable_name)
+        } else {
+            write!(f, "save {} as {}", self.value, self.variable_name)
+        }
+    }
+}
+
+impl SetVariableStmt {
+    /// Whether the statement saves a secret which is written in the script, rather than
+    /// read from an environment variable or another variable.
+    pub fn has_literal_secret(&self) -> bool {
+        self.secret && self.value.has_text_literal()
+    }
+}
+
+/// An expression producing the value saved to a variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Text(String),
+    Number(f64),
+    Bool(bool),
+    Variable(String),
+
+    /// Arithmetic, or concatenation when adding text
+    Binary(Box<Expr>, BinaryOp, Box<Expr>),
+
+    Upper(Box<Expr>),
+    Lower(Box<Expr>),
+    Trim(Box<Expr>),
+
+    /// The characters of the text from a start index (inclusive) to an end index (exclusive)
+    Substring(Box<Expr>, Box<Expr>, Box<Expr>),
+
+    /// The first capture group of a regex (or the whole match if it has no groups) found in some text
+    Match(Box<Expr>, Box<Expr>),
+
+    /// An environment variable, or a variable from a .env file
+    Env(Box<Expr>),
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Text(s) => write!(f, "\"{}\"", s),
+            Expr::Number(n) => write!(f, "{}", n),
+            Expr::Bool(b) => write!(f, "{}", b),
+            Expr::Variable(v) => write!(f, "{}", v),
+            Expr::Binary(lhs, op, rhs) => write!(f, "{} {} {}", lhs, op, rhs),
+            Expr::Upper(e) => write!(f, "upper {}", e),
+            Expr::Lower(e) => write!(f, "lower {}", e),
+            Expr::Trim(e) => write!(f, "trim {}", e),
+            Expr::Substring(e, start, end) => write!(f, "substring {} {} {}", e, start, end),
+            Expr::Match(pattern, e) => write!(f, "match {} in {}", pattern, e),
+            Expr::Env(name) => write!(f, "env {}", name),
+        }
+    }
+}
+
+impl Expr {
+    /// Whether any text in the expression is written out, other than the names of environment variables.
+    pub fn has_text_literal(&self) -> bool {
+        match self {
+            Expr::Text(_) => true,
+            Expr::Number(_) | Expr::Bool(_) | Expr::Variable(_) => false,
+            Expr::Env(name) => !matches!(**name, Expr::Text(_)) && name.has_text_literal(),
+            Expr::Upper(e) | Expr::Lower(e) | Expr::Trim(e) => e.has_text_literal(),
+            Expr::Binary(lhs, _, rhs) | Expr::Match(lhs, rhs) => {
+                lhs.has_text_literal() || rhs.has_text_literal()
+            }
+            Expr::Substring(e, start, end) => {
+                e.has_text_literal() || start.has_text_literal() || end.has_text_literal()
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl std::fmt::Display for BinaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BinaryOp::Add => write!(f, "+"),
+            BinaryOp::Subtract => write!(f, "-"),
+            BinaryOp::Multiply => write!(f, "*"),
+            BinaryOp::Divide => write!(f, "/"),
+        }
     }
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct IfStmt {
-    pub condition: Cmd,
+    pub condition: Condition,
     pub then_branch: CmdStmt,
 }
 
+/// The condition of an if statement.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Holds if the command succeeds without error
+    Cmd(Cmd),
+
+    /// Compares two values, like `username equals "admin"`
+    Compare(Operand, Comparison, Operand),
+
+    /// Holds if the element matching the locator is enabled
+    IsEnabled(CmdParam),
+
+    /// Holds if the element matching the locator is displayed
+    IsDisplayed(CmdParam),
+
+    Not(Box<Condition>),
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+}
+
+impl std::fmt::Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Cmd(cmd) => write!(f, "{}", cmd),
+            Condition::Compare(lhs, cmp, rhs) => write!(f, "{} {} {}", lhs, cmp, rhs),
+            Condition::IsEnabled(cp) => write!(f, "{} is-enabled", cp),
+            Condition::IsDisplayed(cp) => write!(f, "{} is-displayed", cp),
+            Condition::Not(c) => write!(f, "not {}", c),
+            Condition::And(lhs, rhs) => write!(f, "{} and {}", lhs, rhs),
+            Condition::Or(lhs, rhs) => write!(f, "{} or {}", lhs, rhs),
+        }
+    }
+}
+
+/// A value used in a comparison.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Operand {
+    /// Text, a number, a variable or an expression combining them
+    Expr(Expr),
+
+    /// The text of the element matching the locator
+    TextOf(CmdParam),
+}
+
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Expr(e) => write!(f, "{}", e),
+            Operand::TextOf(cp) => write!(f, "text-of {}", cp),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Equals,
+    Contains,
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparison::Equals => write!(f, "equals"),
+            Comparison::Contains => write!(f, "contains"),
+        }
+    }
+}
+
 impl std::fmt::Display for IfStmt {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "if {} then {}", self.condition, self.then_branch)
@@ -58,6 +291,9 @@ impl std::fmt::Display for IfStmt {
 pub struct CmdStmt {
     pub lhs: Cmd,
     pub rhs: Option<(Token, Box<CmdStmt>)>,
+
+    /// The line of the script the statement starts on (for error reporting)
+    pub line: usize,
 }
 
 impl std::fmt::Display for CmdStmt {
@@ -89,7 +325,9 @@ pub enum Cmd {
     Refresh,
 
     /// The try again command lets the process know to start over after the last error handling line.
-    TryAgain,
+    /// Optionally takes the number of times to try again (default once), and the number of
+    /// seconds to wait before each attempt.
+    TryAgain(Option<CmdParam>, Option<CmdParam>),
 
     /// Command for taking a screenshot
     Screenshot,
@@ -112,10 +350,126 @@ pub enum Cmd {
 
     DragTo(CmdParam),
 
-    Upload(CmdParam),
+    /// Uploads one or more files to the located file input (or the file input
+    /// associated with the located element).
+    Upload(Vec<CmdParam>),
 
     AcceptAlert,
     DismissAlert,
+
+    /// Moves the mouse over the located element without clicking.
+    Hover,
+
+    /// Double clicks the located element.
+    DoubleClick,
+
+    /// Right clicks (context clicks) the located element.
+    RightClick,
+
+    /// Clicks at an x, y offset. The offset is from the center of the located
+    /// element, or from the top left of the viewport if no element is located.
+    ClickAt(CmdParam, CmdParam),
+
+    /// Makes sure a checkbox, radio button or switch is checked,
+    /// only clicking it if it isn't already.
+    Check,
+
+    /// Makes sure a checkbox or switch is unchecked,
+    /// only clicking it if it is currently checked.
+    Uncheck,
+
+    /// Flips the state of a checkbox or switch.
+    Toggle,
+
+    /// Command for typing text into some web element without clearing it first.
+    /// The associated string is the provided text.
+    Append(CmdParam),
+
+    /// Command for typing text into some web element one keystroke at a time.
+    /// The associated string is the provided text.
+    TypeSlowly(CmdParam),
+
+    /// Command for emptying a web element like an input.
+    Clear,
+
+    /// Selects an option of a select element by its value attribute.
+    SelectValue(CmdParam),
+
+    /// Selects an option of a select element by its index.
+    SelectIndex(CmdParam),
+
+    /// Deselects all the options of a multi-select element.
+    DeselectAll,
+
+    /// Drags the located element by an x, y offset.
+    DragBy(CmdParam, CmdParam),
+
+    /// Reads the text of the currently open alert to a variable.
+    /// Associated string is the variable name
+    ReadAlertTo(String),
+
+    /// Types text into a prompt dialog.
+    TypeIntoAlert(CmdParam),
+
+    /// Fails if the currently open alert doesn't have the provided text.
+    AssertAlertText(CmdParam),
+
+    /// Navigates back in the browser history.
+    Back,
+
+    /// Navigates forward in the browser history.
+    Forward,
+
+    /// Waits until the current url contains the provided text.
+    WaitForUrl(CmdParam),
+
+    /// Adds a cookie with the provided name and value.
+    SetCookie(CmdParam, CmdParam),
+
+    /// Deletes all cookies.
+    DeleteCookies,
+
+    /// Reads the value of the cookie with the provided name to a variable.
+    ReadCookie(CmdParam, String),
+
+    /// Sets a local storage item with the provided key and value.
+    SetLocalStorage(CmdParam, CmdParam),
+
+    /// Reads the local storage item with the provided key to a variable.
+    ReadLocalStorage(CmdParam, String),
+
+    /// Sets a session storage item with the provided key and value.
+    SetSessionStorage(CmdParam, CmdParam),
+
+    /// Reads the session storage item with the provided key to a variable.
+    ReadSessionStorage(CmdParam, String),
+
+    /// Saves the cookies, local storage and session storage of the current site to a file.
+    SaveSession(CmdParam),
+
+    /// Restores cookies, local storage and session storage saved by save-session.
+    LoadSession(CmdParam),
+
+    /// Runs some javascript, optionally saving the returned value to a variable.
+    /// The currently located element is passed to the script as arguments[0].
+    ExecuteJs(CmdParam, Option<String>),
+
+    /// Scrolls to the bottom of the page (or of the container, when used with under).
+    ScrollToBottom,
+
+    /// Scrolls the page (or the container, when used with under) by an x, y offset.
+    ScrollBy(CmdParam, CmdParam),
+
+    /// Scrolls the located element into view. The optional param is the
+    /// alignment (top, center or bottom), defaulting to center.
+    ScrollIntoView(Option<CmdParam>),
+
+    /// Resizes the browser window to the provided width and height.
+    SetWindowSize(CmdParam, CmdParam),
+
+    /// Quickly checks whether an element matching the locator exists, without
+    /// waiting the way locate does. Mainly useful as an if condition.
+    Exists(CmdParam),
 }
 
 impl std::fmt::Display for Cmd {
@@ -125,7 +479,11 @@ impl std::fmt::Display for Cmd {
             Cmd::Type(cp) => write!(f, "type {}", cp),
             Cmd::Click => write!(f, "click"),
             Cmd::Refresh => write!(f, "refresh"),
-            Cmd::TryAgain => write!(f, "try-again"),
+            Cmd::TryAgain(None, _) => write!(f, "try-again"),
+            Cmd::TryAgain(Some(attempts), None) => write!(f, "try-again {}", attempts),
+            Cmd::TryAgain(Some(attempts), Some(delay)) => {
+                write!(f, "try-again {} {}", attempts, delay)
+            }
             Cmd::Screenshot => write!(f, "screenshot"),
             Cmd::ReadTo(cp) => write!(f, "read-to {}", cp),
             Cmd::Url(cp) => write!(f, "url {}", cp),
@@ -134,9 +492,55 @@ impl std::fmt::Display for Cmd {
             Cmd::LocateNoScroll(cp) => write!(f, "locate-no-scroll {}", cp),
             Cmd::Select(cp) => write!(f, "select {}", cp),
             Cmd::DragTo(cp) => write!(f, "drag-to {}", cp),
-            Cmd::Upload(cp) => write!(f, "upload {}", cp),
+            Cmd::Upload(cps) => write!(
+                f,
+                "upload {}",
+                cps.iter()
+                    .map(|cp| cp.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
             Cmd::AcceptAlert => write!(f, "accept-alert"),
             Cmd::DismissAlert => write!(f, "dismiss-alert"),
+            Cmd::Hover => write!(f, "hover"),
+            Cmd::DoubleClick => write!(f, "double-click"),
+            Cmd::RightClick => write!(f, "right-click"),
+            Cmd::ClickAt(x, y) => write!(f, "click-at {} {}", x, y),
+            Cmd::Check => write!(f, "check"),
+            Cmd::Uncheck => write!(f, "uncheck"),
+            Cmd::Toggle => write!(f, "toggle"),
+            Cmd::Append(cp) => write!(f, "append {}", cp),
+            Cmd::TypeSlowly(cp) => write!(f, "type-slowly {}", cp),
+            Cmd::Clear => write!(f, "clear"),
+            Cmd::SelectValue(cp) => write!(f, "select-value {}", cp),
+            Cmd::SelectIndex(cp) => write!(f, "select-index {}", cp),
+            Cmd::DeselectAll => write!(f, "deselect-all"),
+            Cmd::DragBy(x, y) => write!(f, "drag-by {} {}", x, y),
+            Cmd::ReadAlertTo(v) => write!(f, "read-alert-to {}", v),
+            Cmd::TypeIntoAlert(cp) => write!(f, "type-into-alert {}", cp),
+            Cmd::AssertAlertText(cp) => write!(f, "assert-alert-text {}", cp),
+            Cmd::Back => write!(f, "back"),
+            Cmd::Forward => write!(f, "forward"),
+            Cmd::WaitForUrl(cp) => write!(f, "wait-for-url {}", cp),
+            Cmd::SetCookie(name, value) => write!(f, "set-cookie {} {}", name, value),
+            Cmd::DeleteCookies => write!(f, "delete-cookies"),
+            Cmd::ReadCookie(name, v) => write!(f, "read-cookie {} to {}", name, v),
+            Cmd::SetLocalStorage(key, value) => write!(f, "set-local-storage {} {}", key, value),
+            Cmd::ReadLocalStorage(key, v) => write!(f, "read-local-storage {} to {}", key, v),
+            Cmd::SetSessionStorage(key, value) => {
+                write!(f, "set-session-storage {} {}", key, value)
+            }
+            Cmd::ReadSessionStorage(key, v) => write!(f, "read-session-storage {} to {}", key, v),
+            Cmd::SaveSession(cp) => write!(f, "save-session {}", cp),
+            Cmd::LoadSession(cp) => write!(f, "load-session {}", cp),
+            Cmd::ExecuteJs(cp, Some(v)) => write!(f, "execute-js {} to {}", cp, v),
+            Cmd::ExecuteJs(cp, None) => write!(f, "execute-js {}", cp),
+            Cmd::ScrollToBottom => write!(f, "scroll-to-bottom"),
+            Cmd::ScrollBy(x, y) => write!(f, "scroll-by {} {}", x, y),
+            Cmd::ScrollIntoView(Some(cp)) => write!(f, "scroll-into-view {}", cp),
+            Cmd::ScrollIntoView(None) => write!(f, "scroll-into-view"),
+            Cmd::SetWindowSize(w, h) => write!(f, "set-window-size {} {}", w, h),
+            Cmd::Exists(cp) => write!(f, "exists {}", cp),
         }
     }
 }
@@ -163,6 +567,8 @@ impl TryFrom<Token> for CmdParam {
         match value.token_type {
             TokenType::String(s) => Ok(Self::String(s)),
             TokenType::Variable(v) => Ok(Self::Variable(v)),
+            // Commands take their arguments as text, like the 5 in `chill 5`
+            TokenType::Number(n) => Ok(Self::String(n.to_string())),
             _ => Err("Invalid input".to_owned()),
         }
     }
@@ -172,6 +578,28 @@ pub struct Parser {
     stmts: Vec<Stmt>,
     curr_line: Vec<Token>,
     index: usize,
+
+    /// The try blocks which haven't been closed by an end yet, innermost last
+    open_blocks: Vec<OpenBlock>,
+}
+
+/// The section of a try block the parser is in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum BlockSection {
+    Try,
+    OnError,
+    Finally,
+}
+
+/// A try block which hasn't been closed yet.
+struct OpenBlock {
+    /// The try token, for reporting a block which is never closed
+    token: Token,
+
+    /// Where the try stmt (and its on-error and finally stmts) are in the parsed stmts
+    stmt_indices: Vec<usize>,
+
+    section: BlockSection,
 }
 
 impl Parser {
@@ -180,6 +608,7 @@ impl Parser {
             stmts: vec![],
             curr_line: vec![],
             index: 0,
+            open_blocks: vec![],
         }
     }
 
@@ -193,7 +622,10 @@ impl Parser {
             if self.current_token().unwrap().token_type == TokenType::Eof {
                 break;
             }
-            match self.parse_stmt() {
+            match self
+                .parse_stmt()
+                .and_then(|stmt| self.expect_end_of_line().map(|_| stmt))
+            {
                 Ok(stmt) => self.stmts.push(stmt),
                 Err(e) => {
                     eprintln!("{}", e)
@@ -201,6 +633,22 @@ impl Parser {
             }
             self.index = 0;
         }
+
+        // A try block without an end is a syntax error, so its try, on-error and
+        // finally stmts are left out like any other
+        let mut unclosed = vec![];
+        for block in self.open_blocks.drain(..) {
+            eprintln!(
+                "{}",
+                block.token.error("Expected `end` to close this try block")
+            );
+            unclosed.extend(block.stmt_indices);
+        }
+        unclosed.sort_unstable();
+        for i in unclosed.into_iter().rev() {
+            self.stmts.remove(i);
+        }
+
         let stmts = self.stmts.clone();
         self.stmts.clear();
         stmts
@@ -216,19 +664,81 @@ impl Parser {
         } else if self.advance_on(TokenType::UnderActiveElement).is_some() {
             let cs = self.parse_cmd_stmt()?;
             Ok(Stmt::UnderActiveElement(cs))
+        } else if self.advance_on(TokenType::Whenever).is_some() {
+            let cp = self.parse_cmd_param()?;
+            let _appears_token = self
+                .advance_on(TokenType::Appears)
+                .ok_or(self.error("Expected keyword `appears`"))?;
+            let _then_token = self
+                .advance_on(TokenType::Then)
+                .ok_or(self.error("Expected keyword `then`"))?;
+            let cs = self.parse_cmd_stmt()?;
+            Ok(Stmt::Whenever(cp, cs))
+        } else if self.advance_on(TokenType::Soft).is_some() {
+            let cs = self.parse_cmd_stmt()?;
+            Ok(Stmt::Soft(cs))
+        } else if self.advance_on(TokenType::OnAlert).is_some() {
+            let policy = self
+                .advance_on(TokenType::Variable("n/a".to_owned()))
+                .ok_or(self.error("Expected `accept`, `dismiss` or `ignore`"))?;
+            match policy.token_type {
+                TokenType::Variable(v) if v == "accept" => Ok(Stmt::OnAlert(AlertPolicy::Accept)),
+                TokenType::Variable(v) if v == "dismiss" => Ok(Stmt::OnAlert(AlertPolicy::Dismiss)),
+                TokenType::Variable(v) if v == "ignore" => Ok(Stmt::OnAlert(AlertPolicy::Ignore)),
+                _ => Err(policy.error("Expected `accept`, `dismiss` or `ignore`")),
+            }
         } else if let Some(Token {
             token_type: TokenType::Comment(s),
             ..
         }) = self.advance_on(TokenType::Comment("n/a".to_owned()))
         {
             Ok(Stmt::Comment(s))
+        } else if let Some(token) = self.advance_on(TokenType::Try) {
+            // Checked before the block changes, since a stmt with an error is left out
+            self.expect_end_of_line()?;
+            self.open_blocks.push(OpenBlock {
+                token,
+                stmt_indices: vec![self.stmts.len()],
+                section: BlockSection::Try,
+            });
+            Ok(Stmt::Try)
+        } else if self.advance_on(TokenType::OnError).is_some() {
+            self.expect_end_of_line()?;
+            match self.open_blocks.last_mut() {
+                Some(block) if block.section == BlockSection::Try => {
+                    block.section = BlockSection::OnError;
+                    block.stmt_indices.push(self.stmts.len());
+                    Ok(Stmt::OnError)
+                }
+                Some(_) => {
+                    Err(self
+                        .error("on-error must come before finally, and only once per try block"))
+                }
+                None => Err(self.error("on-error must be inside a try block")),
+            }
+        } else if self.advance_on(TokenType::Finally).is_some() {
+            self.expect_end_of_line()?;
+            match self.open_blocks.last_mut() {
+                Some(block) if block.section != BlockSection::Finally => {
+                    block.section = BlockSection::Finally;
+                    block.stmt_indices.push(self.stmts.len());
+                    Ok(Stmt::Finally)
+                }
+                Some(_) => Err(self.error("Only one finally is allowed per try block")),
+                None => Err(self.error("finally must be inside a try block")),
+            }
+        } else if self.advance_on(TokenType::End).is_some() {
+            self.expect_end_of_line()?;
+            match self.open_blocks.pop() {
+                Some(_) => Ok(Stmt::End),
+                None => Err(self.error("end must close a try block")),
+            }
         } else if self.advance_on(TokenType::CatchError).is_some() {
             let stmt = self.parse_cmd_stmt()?;
             Ok(Stmt::CatchErr(stmt))
-        } else if self.advance_on(TokenType::Save).is_some() {
-            let value = self
-                .advance_on(TokenType::String("n/a".to_owned()))
-                .ok_or(self.error("Expected some txt"))?;
+        } else if let Some(save_token) = self.advance_on(TokenType::Save) {
+            let secret = self.advance_on(TokenType::Secret).is_some();
+            let value = self.parse_expr()?;
             let _as_token = self
                 .advance_on(TokenType::As)
                 .ok_or(self.error("Expected `as`"))?;
@@ -236,19 +746,15 @@ impl Parser {
                 .advance_on(TokenType::Variable("n/a".to_owned()))
                 .ok_or(self.error("Expected a variable name"))?;
 
-            match (variable_name, value) {
-                (
-                    Token {
-                        token_type: TokenType::Variable(variable_name),
-                        ..
-                    },
-                    Token {
-                        token_type: TokenType::String(value),
-                        ..
-                    },
-                ) => Ok(Stmt::SetVariable(SetVariableStmt {
+            match variable_name {
+                Token {
+                    token_type: TokenType::Variable(variable_name),
+                    ..
+                } => Ok(Stmt::SetVariable(SetVariableStmt {
                     variable_name,
                     value,
+                    secret,
+                    line: save_token.line,
                 })),
                 _ => Err(self.error("Error")),
             }
@@ -258,7 +764,7 @@ impl Parser {
     }
 
     pub fn parse_if_stmt(&mut self) -> Result<IfStmt, String> {
-        let condition = self.parse_cmd()?;
+        let condition = self.parse_condition()?;
         let _then_token = self
             .advance_on(TokenType::Then)
             .ok_or(self.error("Expected keyword `then`"))?;
@@ -269,18 +775,176 @@ impl Parser {
         })
     }
 
+    /// Parses the expression of a save statement. `*` and `/` are applied before `+` and `-`,
+    /// and functions like `upper` apply to the value right after them.
+    /// Ex. count + 1
+    pub fn parse_expr(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_term()?;
+        loop {
+            let op = if self.advance_on(TokenType::Plus).is_some() {
+                BinaryOp::Add
+            } else if self.advance_on(TokenType::Minus).is_some() {
+                BinaryOp::Subtract
+            } else {
+                return Ok(expr);
+            };
+            let rhs = self.parse_term()?;
+            expr = Expr::Binary(Box::new(expr), op, Box::new(rhs));
+        }
+    }
+
+    fn parse_term(&mut self) -> Result<Expr, String> {
+        let mut expr = self.parse_unary_expr()?;
+        loop {
+            let op = if self.advance_on(TokenType::Star).is_some() {
+                BinaryOp::Multiply
+            } else if self.advance_on(TokenType::Slash).is_some() {
+                BinaryOp::Divide
+            } else {
+                return Ok(expr);
+            };
+            let rhs = self.parse_unary_expr()?;
+            expr = Expr::Binary(Box::new(expr), op, Box::new(rhs));
+        }
+    }
+
+    fn parse_unary_expr(&mut self) -> Result<Expr, String> {
+        if self.advance_on(TokenType::Upper).is_some() {
+            Ok(Expr::Upper(Box::new(self.parse_unary_expr()?)))
+        } else if self.advance_on(TokenType::Lower).is_some() {
+            Ok(Expr::Lower(Box::new(self.parse_unary_expr()?)))
+        } else if self.advance_on(TokenType::Trim).is_some() {
+            Ok(Expr::Trim(Box::new(self.parse_unary_expr()?)))
+        } else if self.advance_on(TokenType::Env).is_some() {
+            Ok(Expr::Env(Box::new(self.parse_unary_expr()?)))
+        } else if self.advance_on(TokenType::Substring).is_some() {
+            let expr = self.parse_unary_expr()?;
+            let start = self.parse_unary_expr()?;
+            let end = self.parse_unary_expr()?;
+            Ok(Expr::Substring(
+                Box::new(expr),
+                Box::new(start),
+                Box::new(end),
+            ))
+        } else if self.advance_on(TokenType::Match).is_some() {
+            let pattern = self.parse_unary_expr()?;
+            let _in_token = self
+                .advance_on(TokenType::In)
+                .ok_or(self.error("Expected keyword `in`"))?;
+            let expr = self.parse_unary_expr()?;
+            Ok(Expr::Match(Box::new(pattern), Box::new(expr)))
+        } else {
+            let token = self
+                .advance_on_any_of(vec![
+                    TokenType::String("n/a".to_owned()),
+                    TokenType::Number(0.0),
+                    TokenType::True,
+                    TokenType::False,
+                    TokenType::Variable("n/a".to_owned()),
+                ])
+                .ok_or(self.error("Expected a value"))?;
+            match token.token_type {
+                TokenType::String(s) => Ok(Expr::Text(s)),
+                TokenType::Number(n) => Ok(Expr::Number(n)),
+                TokenType::True => Ok(Expr::Bool(true)),
+                TokenType::False => Ok(Expr::Bool(false)),
+                TokenType::Variable(v) => Ok(Expr::Variable(v)),
+                _ => Err(token.error("Expected a value")),
+            }
+        }
+    }
+
+    /// Parses the condition of an if statement. `not` binds tightest, then `and`, then `or`.
+    /// Ex. not "Save" is-enabled or status equals "Paid"
+    pub fn parse_condition(&mut self) -> Result<Condition, String> {
+        let mut condition = self.parse_and_condition()?;
+        while self.advance_on(TokenType::Or).is_some() {
+            let rhs = self.parse_and_condition()?;
+            condition = Condition::Or(Box::new(condition), Box::new(rhs));
+        }
+        Ok(condition)
+    }
+
+    fn parse_and_condition(&mut self) -> Result<Condition, String> {
+        let mut condition = self.parse_unary_condition()?;
+        while self.advance_on(TokenType::And).is_some() {
+            let rhs = self.parse_unary_condition()?;
+            condition = Condition::And(Box::new(condition), Box::new(rhs));
+        }
+        Ok(condition)
+    }
+
+    fn parse_unary_condition(&mut self) -> Result<Condition, String> {
+        if self.advance_on(TokenType::Not).is_some() {
+            return self
+                .parse_unary_condition()
+                .map(|c| Condition::Not(Box::new(c)));
+        }
+
+        // Conditions which start with a value are comparisons or element states,
+        // anything else is a command.
+        let start = self.index;
+        let lhs = if self.advance_on(TokenType::TextOf).is_some() {
+            Operand::TextOf(self.parse_cmd_param()?)
+        } else {
+            match self.parse_expr() {
+                Ok(e) => Operand::Expr(e),
+                Err(_) if self.index == start => {
+                    return self.parse_cmd().map(|cmd| Condition::Cmd(cmd));
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        // Element states take a locator, which is text or a variable
+        let locator = match lhs {
+            Operand::Expr(Expr::Text(ref s)) => Some(CmdParam::String(s.clone())),
+            Operand::Expr(Expr::Variable(ref v)) => Some(CmdParam::Variable(v.clone())),
+            _ => None,
+        };
+        if let Some(cp) = locator {
+            if self.advance_on(TokenType::IsEnabled).is_some() {
+                return Ok(Condition::IsEnabled(cp));
+            } else if self.advance_on(TokenType::IsDisplayed).is_some() {
+                return Ok(Condition::IsDisplayed(cp));
+            }
+        }
+
+        let cmp = if self.advance_on(TokenType::Equals).is_some() {
+            Comparison::Equals
+        } else if self.advance_on(TokenType::Contains).is_some() {
+            Comparison::Contains
+        } else {
+            return Err(self.error("Expected `equals`, `contains`, `is-enabled` or `is-displayed`"));
+        };
+
+        let rhs = if self.advance_on(TokenType::TextOf).is_some() {
+            Operand::TextOf(self.parse_cmd_param()?)
+        } else {
+            Operand::Expr(self.parse_expr()?)
+        };
+
+        Ok(Condition::Compare(lhs, cmp, rhs))
+    }
+
     /// Parses a statement
     /// Ex. locate "Submit" and click
     pub fn parse_cmd_stmt(&mut self) -> Result<CmdStmt, String> {
+        let line = self.current_token().map(|t| t.line).unwrap_or_default();
         let lhs = self.parse_cmd()?;
         if let Some(and_token) = self.advance_on(TokenType::And) {
             let rhs = self.parse_cmd_stmt()?;
             Ok(CmdStmt {
                 lhs,
                 rhs: Some((and_token, Box::new(rhs))),
+                line,
             })
         } else {
-            Ok(CmdStmt { lhs, rhs: None })
+            Ok(CmdStmt {
+                lhs,
+                rhs: None,
+                line,
+            })
         }
     }
 
@@ -288,14 +952,17 @@ impl Parser {
         self.advance_on_any_of(vec![
             TokenType::String("n/a".to_owned()),
             TokenType::Variable("n/a".to_owned()),
+            TokenType::Number(0.0),
         ])
-        .ok_or(self.error("Expected variable or text"))?
+        .ok_or(self.error("Expected variable, text or number"))?
         .try_into()
     }
 
     pub fn parse_cmd(&mut self) -> Result<Cmd, String> {
         if self.advance_on(TokenType::Locate).is_some() {
             self.parse_cmd_param().map(|cp| Cmd::Locate(cp))
+        } else if self.advance_on(TokenType::Exists).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::Exists(cp))
         } else if self.advance_on(TokenType::LocateNoScroll).is_some() {
             self.parse_cmd_param().map(|cp| Cmd::LocateNoScroll(cp))
         } else if self.advance_on(TokenType::Type).is_some() {
@@ -312,8 +979,26 @@ impl Parser {
                 } => Ok(Cmd::ReadTo(v)),
                 _ => Err(self.error("Expected Variable")),
             }
+        } else if self.advance_on(TokenType::ReadAlertTo).is_some() {
+            let var = self
+                .advance_on(TokenType::Variable("n/a".to_owned()))
+                .ok_or(self.error("Expected Variable"))?;
+
+            match var {
+                Token {
+                    token_type: TokenType::Variable(v),
+                    ..
+                } => Ok(Cmd::ReadAlertTo(v)),
+                _ => Err(self.error("Expected Variable")),
+            }
+        } else if self.advance_on(TokenType::TypeIntoAlert).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::TypeIntoAlert(cp))
+        } else if self.advance_on(TokenType::AssertAlertText).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::AssertAlertText(cp))
         } else if self.advance_on(TokenType::Url).is_some() {
             self.parse_cmd_param().map(|cp| Cmd::Url(cp))
+        } else if self.advance_on(TokenType::WaitForUrl).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::WaitForUrl(cp))
         } else if self.advance_on(TokenType::Press).is_some() {
             self.parse_cmd_param().map(|cp| Cmd::Press(cp))
         } else if self.advance_on(TokenType::Chill).is_some() {
@@ -323,21 +1008,122 @@ impl Parser {
         } else if self.advance_on(TokenType::DragTo).is_some() {
             self.parse_cmd_param().map(|cp| Cmd::DragTo(cp))
         } else if self.advance_on(TokenType::Upload).is_some() {
-            self.parse_cmd_param().map(|cp| Cmd::Upload(cp))
+            let mut cps = vec![self.parse_cmd_param()?];
+            while let Ok(cp) = self.parse_cmd_param() {
+                cps.push(cp);
+            }
+            Ok(Cmd::Upload(cps))
+        } else if self.advance_on(TokenType::SelectValue).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::SelectValue(cp))
+        } else if self.advance_on(TokenType::SelectIndex).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::SelectIndex(cp))
+        } else if self.advance_on(TokenType::Append).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::Append(cp))
+        } else if self.advance_on(TokenType::TypeSlowly).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::TypeSlowly(cp))
+        } else if self.advance_on(TokenType::SetCookie).is_some() {
+            let name = self.parse_cmd_param()?;
+            let value = self.parse_cmd_param()?;
+            Ok(Cmd::SetCookie(name, value))
+        } else if self.advance_on(TokenType::ReadCookie).is_some() {
+            let name = self.parse_cmd_param()?;
+            let var = self.parse_to_variable()?;
+            Ok(Cmd::ReadCookie(name, var))
+        } else if self.advance_on(TokenType::SetLocalStorage).is_some() {
+            let key = self.parse_cmd_param()?;
+            let value = self.parse_cmd_param()?;
+            Ok(Cmd::SetLocalStorage(key, value))
+        } else if self.advance_on(TokenType::ReadLocalStorage).is_some() {
+            let key = self.parse_cmd_param()?;
+            let var = self.parse_to_variable()?;
+            Ok(Cmd::ReadLocalStorage(key, var))
+        } else if self.advance_on(TokenType::SetSessionStorage).is_some() {
+            let key = self.parse_cmd_param()?;
+            let value = self.parse_cmd_param()?;
+            Ok(Cmd::SetSessionStorage(key, value))
+        } else if self.advance_on(TokenType::ReadSessionStorage).is_some() {
+            let key = self.parse_cmd_param()?;
+            let var = self.parse_to_variable()?;
+            Ok(Cmd::ReadSessionStorage(key, var))
+        } else if self.advance_on(TokenType::SaveSession).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::SaveSession(cp))
+        } else if self.advance_on(TokenType::LoadSession).is_some() {
+            self.parse_cmd_param().map(|cp| Cmd::LoadSession(cp))
+        } else if self.advance_on(TokenType::ExecuteJs).is_some() {
+            let script = self.parse_cmd_param()?;
+            let var = match self.current_token() {
+                Some(token) if token.token_type == TokenType::To => Some(self.parse_to_variable()?),
+                _ => None,
+            };
+            Ok(Cmd::ExecuteJs(script, var))
+        } else if self.advance_on(TokenType::ScrollBy).is_some() {
+            let x = self.parse_cmd_param()?;
+            let y = self.parse_cmd_param()?;
+            Ok(Cmd::ScrollBy(x, y))
+        } else if self.advance_on(TokenType::TryAgain).is_some() {
+            let attempts = self.parse_cmd_param().ok();
+            let delay = match attempts {
+                Some(_) => self.parse_cmd_param().ok(),
+                None => None,
+            };
+            Ok(Cmd::TryAgain(attempts, delay))
+        } else if self.advance_on(TokenType::ScrollIntoView).is_some() {
+            Ok(Cmd::ScrollIntoView(self.parse_cmd_param().ok()))
+        } else if self.advance_on(TokenType::SetWindowSize).is_some() {
+            let width = self.parse_cmd_param()?;
+            let height = self.parse_cmd_param()?;
+            Ok(Cmd::SetWindowSize(width, height))
+        } else if self.advance_on(TokenType::ClickAt).is_some() {
+            let x = self.parse_cmd_param()?;
+            let y = self.parse_cmd_param()?;
+            Ok(Cmd::ClickAt(x, y))
+        } else if self.advance_on(TokenType::DragBy).is_some() {
+            let x = self.parse_cmd_param()?;
+            let y = self.parse_cmd_param()?;
+            Ok(Cmd::DragBy(x, y))
         } else {
             let token = self.advance_on_any();
             match token.token_type {
                 TokenType::Click => Ok(Cmd::Click),
                 TokenType::Refresh => Ok(Cmd::Refresh),
-                TokenType::TryAgain => Ok(Cmd::TryAgain),
                 TokenType::Screenshot => Ok(Cmd::Screenshot),
                 TokenType::AcceptAlert => Ok(Cmd::AcceptAlert),
                 TokenType::DismissAlert => Ok(Cmd::DismissAlert),
+                TokenType::Hover => Ok(Cmd::Hover),
+                TokenType::DoubleClick => Ok(Cmd::DoubleClick),
+                TokenType::RightClick => Ok(Cmd::RightClick),
+                TokenType::Check => Ok(Cmd::Check),
+                TokenType::Uncheck => Ok(Cmd::Uncheck),
+                TokenType::Toggle => Ok(Cmd::Toggle),
+                TokenType::Clear => Ok(Cmd::Clear),
+                TokenType::DeselectAll => Ok(Cmd::DeselectAll),
+                TokenType::Back => Ok(Cmd::Back),
+                TokenType::Forward => Ok(Cmd::Forward),
+                TokenType::DeleteCookies => Ok(Cmd::DeleteCookies),
+                TokenType::ScrollToBottom => Ok(Cmd::ScrollToBottom),
                 _ => Err(token.error("Expected command")),
             }
         }
     }
 
+    /// Parses the `to <variable>` ending of commands which save a value to a variable.
+    fn parse_to_variable(&mut self) -> Result<String, String> {
+        let _to_token = self
+            .advance_on(TokenType::To)
+            .ok_or(self.error("Expected `to`"))?;
+        let var = self
+            .advance_on(TokenType::Variable("n/a".to_owned()))
+            .ok_or(self.error("Expected Variable"))?;
+
+        match var {
+            Token {
+                token_type: TokenType::Variable(v),
+                ..
+            } => Ok(v),
+            _ => Err(self.error("Expected Variable")),
+        }
+    }
+
     fn advance_on(&mut self, tt: TokenType) -> Option<Token> {
         if let Some(token) = self.current_token() {
             if token.token_type == tt {
@@ -365,6 +1151,18 @@ impl Parser {
             .unwrap()
     }
 
+    /// Makes sure nothing is left on the line after a statement, so a mistyped
+    /// argument isn't silently ignored.
+    fn expect_end_of_line(&self) -> Result<(), String> {
+        match self.current_token() {
+            Some(token) if token.token_type != TokenType::Eof => Err(token.error(&format!(
+                "Unexpected `{}` after the end of the statement",
+                token.token_type
+            ))),
+            _ => Ok(()),
+        }
+    }
+
     fn error(&self, msg: &str) -> String {
         self.current_token()
             .map(|t| t.error(msg))
@@ -380,3 +1178,171 @@ impl Parser {
         self.curr_line.get(self.index - 1).unwrap().clone()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scanner::Scanner;
+
+    fn parse(src: &str) -> Vec<Stmt> {
+        let tokens = Scanner::from_src(src.to_owned()).scan();
+        Parser::new().parse(tokens)
+    }
+
+    fn parse_save(src: &str) -> Expr {
+        match parse(src).pop() {
+            Some(Stmt::SetVariable(sv)) => sv.value,
+            other => panic!("Expected a save statement, but parsed {:?}", other),
+        }
+    }
+
+    fn var(name: &str) -> Box<Expr> {
+        Box::new(Expr::Variable(name.to_owned()))
+    }
+
+    fn num(n: f64) -> Box<Expr> {
+        Box::new(Expr::Number(n))
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        assert_eq!(
+            parse_save("save a + b * 2 as x"),
+            Expr::Binary(
+                var("a"),
+                BinaryOp::Add,
+                Box::new(Expr::Binary(var("b"), BinaryOp::Multiply, num(2.0)))
+            )
+        );
+        assert_eq!(
+            parse_save("save a / 2 - b as x"),
+            Expr::Binary(
+                Box::new(Expr::Binary(var("a"), BinaryOp::Divide, num(2.0))),
+                BinaryOp::Subtract,
+                var("b")
+            )
+        );
+    }
+
+    #[test]
+    fn operators_are_left_associative() {
+        assert_eq!(
+            parse_save("save 10 - 4 - 3 as x"),
+            Expr::Binary(
+                Box::new(Expr::Binary(num(10.0), BinaryOp::Subtract, num(4.0))),
+                BinaryOp::Subtract,
+                num(3.0)
+            )
+        );
+    }
+
+    #[test]
+    fn functions_apply_to_the_value_right_after_them() {
+        assert_eq!(
+            parse_save("save upper name + \"!\" as x"),
+            Expr::Binary(
+                Box::new(Expr::Upper(var("name"))),
+                BinaryOp::Add,
+                Box::new(Expr::Text("!".to_owned()))
+            )
+        );
+        assert_eq!(
+            parse_save("save substring msg 0 5 as x"),
+            Expr::Substring(var("msg"), num(0.0), num(5.0))
+        );
+        assert_eq!(
+            parse_save("save match \"#(\\d+)\" in msg as x"),
+            Expr::Match(Box::new(Expr::Text("#(\\d+)".to_owned())), var("msg"))
+        );
+    }
+
+    #[test]
+    fn save_statements_display_as_they_were_written() {
+        for src in [
+            "save count + 1 as count",
+            "save lower name + \"@test.com\" as email",
+            "save secret env \"ADMIN_PASSWORD\" as pw",
+        ] {
+            assert_eq!(parse(src)[0].to_string(), src);
+        }
+    }
+
+    #[test]
+    fn save_statements_know_their_line() {
+        let stmts = parse("click\n\nsave count + 1 as count");
+        assert_eq!(stmts.last().and_then(|stmt| stmt.line()), Some(3));
+    }
+
+    #[test]
+    fn conditions_compare_numbers() {
+        match parse("if count equals 3 then click").pop() {
+            Some(Stmt::If(is)) => assert_eq!(
+                is.condition,
+                Condition::Compare(
+                    Operand::Expr(Expr::Variable("count".to_owned())),
+                    Comparison::Equals,
+                    Operand::Expr(Expr::Number(3.0))
+                )
+            ),
+            other => panic!("Expected an if statement, but parsed {:?}", other),
+        }
+    }
+
+    #[test]
+    fn try_block_sections_must_be_inside_a_try_block() {
+        assert!(parse("on-error").is_empty());
+        assert!(parse("finally").is_empty());
+        assert!(parse("end").is_empty());
+        assert_eq!(parse("try\nclick\non-error\nscreenshot").len(), 2);
+        assert_eq!(parse("try\nclick\non-error\nscreenshot\nend").len(), 5);
+    }
+
+    fn parse_cmd(src: &str) -> Cmd {
+        match parse(src).pop() {
+            Some(Stmt::Cmd(cs)) => cs.lhs,
+            other => panic!("Expected a command, but parsed {:?}", other),
+        }
+    }
+
+    fn text(s: &str) -> CmdParam {
+        CmdParam::String(s.to_owned())
+    }
+
+    #[test]
+    fn commands_take_numbers_as_arguments() {
+        assert_eq!(parse_cmd("chill 5"), Cmd::Chill(text("5")));
+        assert_eq!(parse_cmd("select-index 2"), Cmd::SelectIndex(text("2")));
+        assert_eq!(
+            parse_cmd("scroll-by 0 500"),
+            Cmd::ScrollBy(text("0"), text("500"))
+        );
+        assert_eq!(
+            parse_cmd("click-at 10.5 20"),
+            Cmd::ClickAt(text("10.5"), text("20"))
+        );
+        assert_eq!(
+            parse_cmd("set-window-size 1280 800"),
+            Cmd::SetWindowSize(text("1280"), text("800"))
+        );
+        assert_eq!(
+            parse_cmd("drag-by -50 0"),
+            Cmd::DragBy(text("-50"), text("0"))
+        );
+        assert_eq!(
+            parse_cmd("try-again 3"),
+            Cmd::TryAgain(Some(text("3")), None)
+        );
+        assert_eq!(
+            parse_cmd("try-again 3 2"),
+            Cmd::TryAgain(Some(text("3")), Some(text("2")))
+        );
+    }
+
+    #[test]
+    fn leftover_tokens_are_an_error() {
+        assert!(parse("chill 5 6").is_empty());
+        assert!(parse("click \"Submit\"").is_empty());
+        assert!(parse("try again").is_empty());
+        assert_eq!(parse("try\nclick\nend").len(), 3);
+    }
+}
@@ -21,6 +21,51 @@ pub enum TokenType {
     DismissAlert,
     Under,
     UnderActiveElement,
+    Soft,
+    Whenever,
+    Appears,
+    Hover,
+    DoubleClick,
+    RightClick,
+    ClickAt,
+    Check,
+    Uncheck,
+    Toggle,
+    Append,
+    TypeSlowly,
+    Clear,
+    SelectValue,
+    SelectIndex,
+    DeselectAll,
+    DragBy,
+    ReadAlertTo,
+    TypeIntoAlert,
+    AssertAlertText,
+    OnAlert,
+    Back,
+    Forward,
+    WaitForUrl,
+    SetCookie,
+    DeleteCookies,
+    ReadCookie,
+    SetLocalStorage,
+    ReadLocalStorage,
+    SetSessionStorage,
+    ReadSessionStorage,
+    SaveSession,
+    LoadSession,
+    ExecuteJs,
+    ScrollToBottom,
+    ScrollBy,
+    ScrollIntoView,
+    SetWindowSize,
+    Exists,
+
+    // Blocks
+    Try,
+    OnError,
+    Finally,
+    End,
 
     // Literals (the associated string is the string literal)
     String(String),
@@ -29,11 +74,38 @@ pub enum TokenType {
     If,
     Then,
     And,
+    Or,
+    Not,
+
+    // Conditions
+    Equals,
+    Contains,
+    TextOf,
+    IsEnabled,
+    IsDisplayed,
 
     // Variable (the associated string is the variable name)
     Variable(String),
     Save,
     As,
+    To,
+
+    // Expressions
+    Number(f64),
+    True,
+    False,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Upper,
+    Lower,
+    Trim,
+    Substring,
+    Match,
+    In,
+    Env,
+    Secret,
 
     // Comment token
     Comment(String),
@@ -74,7 +146,29 @@ impl std::fmt::Display for TokenType {
             TokenType::If => "if",
             TokenType::Then => "then",
             TokenType::And => "and",
+            TokenType::Or => "or",
+            TokenType::Not => "not",
+            TokenType::Equals => "equals",
+            TokenType::Contains => "contains",
+            TokenType::TextOf => "text-of",
+            TokenType::IsEnabled => "is-enabled",
+            TokenType::IsDisplayed => "is-displayed",
             TokenType::Variable(v) => v,
+            TokenType::Number(n) => return write!(f, "{}", n),
+            TokenType::True => "true",
+            TokenType::False => "false",
+            TokenType::Plus => "+",
+            TokenType::Minus => "-",
+            TokenType::Star => "*",
+            TokenType::Slash => "/",
+            TokenType::Upper => "upper",
+            TokenType::Lower => "lower",
+            TokenType::Trim => "trim",
+            TokenType::Substring => "substring",
+            TokenType::Match => "match",
+            TokenType::In => "in",
+            TokenType::Env => "env",
+            TokenType::Secret => "secret",
             TokenType::Eof => "eof",
             TokenType::Eol => "eol",
             TokenType::Save => "save",
@@ -91,6 +185,50 @@ impl std::fmt::Display for TokenType {
             TokenType::DismissAlert => "dismiss-alert",
             TokenType::Under => "under",
             TokenType::UnderActiveElement => "under-active-element",
+            TokenType::Soft => "soft",
+            TokenType::Whenever => "whenever",
+            TokenType::Appears => "appears",
+            TokenType::Hover => "hover",
+            TokenType::DoubleClick => "double-click",
+            TokenType::RightClick => "right-click",
+            TokenType::ClickAt => "click-at",
+            TokenType::Check => "check",
+            TokenType::Uncheck => "uncheck",
+            TokenType::Toggle => "toggle",
+            TokenType::Append => "append",
+            TokenType::TypeSlowly => "type-slowly",
+            TokenType::Clear => "clear",
+            TokenType::SelectValue => "select-value",
+            TokenType::SelectIndex => "select-index",
+            TokenType::DeselectAll => "deselect-all",
+            TokenType::DragBy => "drag-by",
+            TokenType::ReadAlertTo => "read-alert-to",
+            TokenType::TypeIntoAlert => "type-into-alert",
+            TokenType::AssertAlertText => "assert-alert-text",
+            TokenType::OnAlert => "on-alert",
+            TokenType::Back => "back",
+            TokenType::Forward => "forward",
+            TokenType::WaitForUrl => "wait-for-url",
+            TokenType::SetCookie => "set-cookie",
+            TokenType::DeleteCookies => "delete-cookies",
+            TokenType::ReadCookie => "read-cookie",
+            TokenType::SetLocalStorage => "set-local-storage",
+            TokenType::ReadLocalStorage => "read-local-storage",
+            TokenType::SetSessionStorage => "set-session-storage",
+            TokenType::ReadSessionStorage => "read-session-storage",
+            TokenType::To => "to",
+            TokenType::SaveSession => "save-session",
+            TokenType::LoadSession => "load-session",
+            TokenType::ExecuteJs => "execute-js",
+            TokenType::ScrollToBottom => "scroll-to-bottom",
+            TokenType::ScrollBy => "scroll-by",
+            TokenType::ScrollIntoView => "scroll-into-view",
+            TokenType::SetWindowSize => "set-window-size",
+            TokenType::Exists => "exists",
+            TokenType::Try => "try",
+            TokenType::OnError => "on-error",
+            TokenType::Finally => "finally",
+            TokenType::End => "end",
         };
 
         write!(f, "{}", lexeme)
@@ -196,6 +334,13 @@ impl Scanner {
             "if" if !self.in_quotes => Some(self.token(TokenType::If)),
             "then" if !self.in_quotes => Some(self.token(TokenType::Then)),
             "and" if !self.in_quotes => Some(self.token(TokenType::And)),
+            "or" if !self.in_quotes => Some(self.token(TokenType::Or)),
+            "not" if !self.in_quotes => Some(self.token(TokenType::Not)),
+            "equals" if !self.in_quotes => Some(self.token(TokenType::Equals)),
+            "contains" if !self.in_quotes => Some(self.token(TokenType::Contains)),
+            "text-of" if !self.in_quotes => Some(self.token(TokenType::TextOf)),
+            "is-enabled" if !self.in_quotes => Some(self.token(TokenType::IsEnabled)),
+            "is-displayed" if !self.in_quotes => Some(self.token(TokenType::IsDisplayed)),
             "read-to" if !self.in_quotes => Some(self.token(TokenType::ReadTo)),
             "save" if !self.in_quotes => Some(self.token(TokenType::Save)),
             "as" if !self.in_quotes => Some(self.token(TokenType::As)),
@@ -212,6 +357,70 @@ impl Scanner {
             "under-active-element" if !self.in_quotes => {
                 Some(self.token(TokenType::UnderActiveElement))
             }
+            "soft" if !self.in_quotes => Some(self.token(TokenType::Soft)),
+            "whenever" if !self.in_quotes => Some(self.token(TokenType::Whenever)),
+            "appears" if !self.in_quotes => Some(self.token(TokenType::Appears)),
+            "hover" if !self.in_quotes => Some(self.token(TokenType::Hover)),
+            "double-click" if !self.in_quotes => Some(self.token(TokenType::DoubleClick)),
+            "right-click" if !self.in_quotes => Some(self.token(TokenType::RightClick)),
+            "click-at" if !self.in_quotes => Some(self.token(TokenType::ClickAt)),
+            "check" if !self.in_quotes => Some(self.token(TokenType::Check)),
+            "uncheck" if !self.in_quotes => Some(self.token(TokenType::Uncheck)),
+            "toggle" if !self.in_quotes => Some(self.token(TokenType::Toggle)),
+            "append" if !self.in_quotes => Some(self.token(TokenType::Append)),
+            "type-slowly" if !self.in_quotes => Some(self.token(TokenType::TypeSlowly)),
+            "clear" if !self.in_quotes => Some(self.token(TokenType::Clear)),
+            "select-value" if !self.in_quotes => Some(self.token(TokenType::SelectValue)),
+            "select-index" if !self.in_quotes => Some(self.token(TokenType::SelectIndex)),
+            "deselect-all" if !self.in_quotes => Some(self.token(TokenType::DeselectAll)),
+            "drag-by" if !self.in_quotes => Some(self.token(TokenType::DragBy)),
+            "read-alert-to" if !self.in_quotes => Some(self.token(TokenType::ReadAlertTo)),
+            "type-into-alert" if !self.in_quotes => Some(self.token(TokenType::TypeIntoAlert)),
+            "assert-alert-text" if !self.in_quotes => Some(self.token(TokenType::AssertAlertText)),
+            "on-alert" if !self.in_quotes => Some(self.token(TokenType::OnAlert)),
+            "back" if !self.in_quotes => Some(self.token(TokenType::Back)),
+            "forward" if !self.in_quotes => Some(self.token(TokenType::Forward)),
+            "wait-for-url" if !self.in_quotes => Some(self.token(TokenType::WaitForUrl)),
+            "set-cookie" if !self.in_quotes => Some(self.token(TokenType::SetCookie)),
+            "delete-cookies" if !self.in_quotes => Some(self.token(TokenType::DeleteCookies)),
+            "read-cookie" if !self.in_quotes => Some(self.token(TokenType::ReadCookie)),
+            "set-local-storage" if !self.in_quotes => Some(self.token(TokenType::SetLocalStorage)),
+            "read-local-storage" if !self.in_quotes => {
+                Some(self.token(TokenType::ReadLocalStorage))
+            }
+            "set-session-storage" if !self.in_quotes => {
+                Some(self.token(TokenType::SetSessionStorage))
+            }
+            "read-session-storage" if !self.in_quotes => {
+                Some(self.token(TokenType::ReadSessionStorage))
+            }
+            "to" if !self.in_quotes => Some(self.token(TokenType::To)),
+            "save-session" if !self.in_quotes => Some(self.token(TokenType::SaveSession)),
+            "load-session" if !self.in_quotes => Some(self.token(TokenType::LoadSession)),
+            "execute-js" if !self.in_quotes => Some(self.token(TokenType::ExecuteJs)),
+            "scroll-to-bottom" if !self.in_quotes => Some(self.token(TokenType::ScrollToBottom)),
+            "scroll-by" if !self.in_quotes => Some(self.token(TokenType::ScrollBy)),
+            "scroll-into-view" if !self.in_quotes => Some(self.token(TokenType::ScrollIntoView)),
+            "set-window-size" if !self.in_quotes => Some(self.token(TokenType::SetWindowSize)),
+            "exists" if !self.in_quotes => Some(self.token(TokenType::Exists)),
+            "try" if !self.in_quotes => Some(self.token(TokenType::Try)),
+            "on-error" if !self.in_quotes => Some(self.token(TokenType::OnError)),
+            "finally" if !self.in_quotes => Some(self.token(TokenType::Finally)),
+            "end" if !self.in_quotes => Some(self.token(TokenType::End)),
+            "true" if !self.in_quotes => Some(self.token(TokenType::True)),
+            "false" if !self.in_quotes => Some(self.token(TokenType::False)),
+            "+" if !self.in_quotes => Some(self.token(TokenType::Plus)),
+            "-" if !self.in_quotes => Some(self.token(TokenType::Minus)),
+            "*" if !self.in_quotes => Some(self.token(TokenType::Star)),
+            "/" if !self.in_quotes => Some(self.token(TokenType::Slash)),
+            "upper" if !self.in_quotes => Some(self.token(TokenType::Upper)),
+            "lower" if !self.in_quotes => Some(self.token(TokenType::Lower)),
+            "trim" if !self.in_quotes => Some(self.token(TokenType::Trim)),
+            "substring" if !self.in_quotes => Some(self.token(TokenType::Substring)),
+            "match" if !self.in_quotes => Some(self.token(TokenType::Match)),
+            "in" if !self.in_quotes => Some(self.token(TokenType::In)),
+            "env" if !self.in_quotes => Some(self.token(TokenType::Env)),
+            "secret" if !self.in_quotes => Some(self.token(TokenType::Secret)),
             // If we get an entire string literal, stript the quotes and construct the token
             word if word.starts_with("\"")
                 && word.ends_with("\"")
@@ -271,6 +480,11 @@ impl Scanner {
                 self.string_literal_buffer.push(' ');
                 None
             }
+            // Number literals
+            word if word.chars().any(|c| c.is_ascii_digit()) && word.parse::<f64>().is_ok() => {
+                // This unwrap is safe because we checked the word parses in the match guard.
+                Some(self.token(TokenType::Number(word.parse().unwrap())))
+            }
             word => Some(self.token(TokenType::Variable(word.to_owned()))),
         }
     }
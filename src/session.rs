@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use thirtyfour::Cookie;
+
+/// A snapshot of the browser state for a site, saved by the save-session command so later
+/// scripts can restore it (usually to skip logging in).
+#[derive(Serialize, Deserialize)]
+pub struct SavedSession {
+    /// The url of the page the session was saved on
+    pub url: String,
+
+    pub cookies: Vec<SavedCookie>,
+
+    /// Local storage items for the site
+    pub local_storage: HashMap<String, String>,
+
+    /// Session storage items for the site
+    pub session_storage: HashMap<String, String>,
+}
+
+/// Everything about a cookie needed to restore it, so a login saved on one subdomain
+/// still works on the others and persistent cookies stay persistent.
+#[derive(Serialize, Deserialize)]
+pub struct SavedCookie {
+    pub name: String,
+    pub value: String,
+    pub path: Option<String>,
+
+    /// Set for cookies shared with subdomains, otherwise the cookie only belongs to the host it was set on
+    #[serde(default)]
+    pub domain: Option<String>,
+
+    /// When the cookie expires, in seconds since the unix epoch. Session cookies don't have one.
+    #[serde(default)]
+    pub expiry: Option<i64>,
+
+    pub secure: Option<bool>,
+    pub http_only: Option<bool>,
+
+    /// Strict, Lax or None
+    #[serde(default)]
+    pub same_site: Option<String>,
+}
+
+impl From<&Cookie<'_>> for SavedCookie {
+    fn from(cookie: &Cookie<'_>) -> Self {
+        Self {
+            name: cookie.name().to_owned(),
+            value: cookie.value().to_owned(),
+            path: cookie.path().map(|p| p.to_owned()),
+            domain: cookie.domain().map(|d| d.to_owned()),
+            expiry: cookie.expires_datetime().map(|dt| dt.unix_timestamp()),
+            secure: cookie.secure(),
+            http_only: cookie.http_only(),
+            same_site: cookie.same_site().map(|s| s.to_string()),
+        }
+    }
+}
+
+impl From<SavedCookie> for Cookie<'static> {
+    /// The cookie is rebuilt from its Set-Cookie header, which carries every field.
+    fn from(saved: SavedCookie) -> Self {
+        let mut header = format!("{}={}", saved.name, saved.value);
+        if let Some(ref domain) = saved.domain {
+            header.push_str(&format!("; Domain={}", domain));
+        }
+        if let Some(ref path) = saved.path {
+            header.push_str(&format!("; Path={}", path));
+        }
+        if let Some(expiry) = saved.expiry {
+            header.push_str(&format!("; Expires={}", http_date(expiry)));
+        }
+        if saved.secure == Some(true) {
+            header.push_str("; Secure");
+        }
+        if saved.http_only == Some(true) {
+            header.push_str("; HttpOnly");
+        }
+        if let Some(ref same_site) = saved.same_site {
+            header.push_str(&format!("; SameSite={}", same_site));
+        }
+
+        Cookie::parse(header).unwrap_or_else(|_| {
+            // Fall back to the name and value if a field doesn't parse
+            let mut cookie = Cookie::new(saved.name, saved.value);
+            if let Some(path) = saved.path {
+                cookie.set_path(path);
+            }
+            cookie.set_secure(saved.secure);
+            cookie.set_http_only(saved.http_only);
+            cookie
+        })
+    }
+}
+
+/// Formats seconds since the unix epoch as an http date, like "Wed, 21 Oct 2015 07:28:00 GMT".
+fn http_date(timestamp: i64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+
+    let days = timestamp.div_euclid(86400);
+    let secs = timestamp.rem_euclid(86400);
+
+    // Converts days since the epoch to a date in the (proleptic) Gregorian calendar.
+    // See http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{}, {:02} {} {} {:02}:{:02}:{:02} GMT",
+        // The epoch was a Thursday
+        WEEKDAYS[days.rem_euclid(7) as usize],
+        day,
+        MONTHS[(month - 1) as usize],
+        year,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_http_dates() {
+        assert_eq!(http_date(0), "Thu, 01 Jan 1970 00:00:00 GMT");
+        assert_eq!(http_date(951782400), "Tue, 29 Feb 2000 00:00:00 GMT");
+        assert_eq!(http_date(1445412480), "Wed, 21 Oct 2015 07:28:00 GMT");
+    }
+
+    #[test]
+    fn restores_every_field() {
+        let saved = SavedCookie {
+            name: "session".to_owned(),
+            value: "abc=123".to_owned(),
+            path: Some("/app".to_owned()),
+            domain: Some("example.com".to_owned()),
+            expiry: Some(1445412480),
+            secure: Some(true),
+            http_only: Some(true),
+            same_site: Some("Lax".to_owned()),
+        };
+        let cookie: Cookie = saved.into();
+        let restored = SavedCookie::from(&cookie);
+
+        assert_eq!(restored.name, "session");
+        assert_eq!(restored.value, "abc=123");
+        assert_eq!(restored.path.as_deref(), Some("/app"));
+        assert_eq!(restored.domain.as_deref(), Some("example.com"));
+        assert_eq!(restored.expiry, Some(1445412480));
+        assert_eq!(restored.secure, Some(true));
+        assert_eq!(restored.http_only, Some(true));
+        assert_eq!(restored.same_site.as_deref(), Some("Lax"));
+    }
+}
@@ -4,6 +4,8 @@ use chrono::Utc;
 use sailfish::TemplateOnce;
 use serde::{Deserialize, Serialize};
 
+use crate::error::ErrorKind;
+
 #[derive(Serialize, Deserialize)]
 pub struct ExecutedStmt {
     /// The text representation of the executed stmt
@@ -12,9 +14,57 @@ pub struct ExecutedStmt {
     /// An error that occured while executing the statment.
     pub error: Option<String>,
 
+    /// What kind of error occured, so failures can be grouped
+    /// without parsing the error message.
+    #[serde(default)]
+    pub error_kind: Option<ErrorKind>,
+
+    /// The line of the script the statement came from.
+    #[serde(default)]
+    pub line: Option<usize>,
+
     /// Path to screenshots generated as part of the command exucution,
     /// saved as png.
     pub screenshots: Vec<Vec<u8>>,
+
+    /// Extra information about how the statement was executed
+    /// (for example, which strategy a drag and drop used).
+    #[serde(default)]
+    pub notes: Vec<String>,
+
+    /// Statements nested under this one (for example, the statements
+    /// re-executed by an attempt of try-again).
+    #[serde(default)]
+    pub children: Vec<ExecutedStmt>,
+}
+
+impl ExecutedStmt {
+    /// An entry which only groups other statements.
+    pub fn group(text: String) -> Self {
+        Self {
+            text,
+            error: None,
+            error_kind: None,
+            line: None,
+            screenshots: vec![],
+            notes: vec![],
+            children: vec![],
+        }
+    }
+}
+
+/// A failure which was recorded without stopping the script.
+#[derive(Serialize, Deserialize)]
+pub struct SoftFailure {
+    /// The text representation of the failed stmt
+    pub text: String,
+
+    pub error: String,
+
+    pub error_kind: ErrorKind,
+
+    /// The line of the script the statement came from.
+    pub line: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize)] // automatically implement `TemplateOnce` trait
@@ -31,6 +81,16 @@ pub struct Report {
 
     /// The test reported
     pub executed_stmts: Vec<ExecutedStmt>,
+
+    /// Failures which didn't stop the script. The script
+    /// still failed if there are any.
+    #[serde(default)]
+    pub soft_failures: Vec<SoftFailure>,
+
+    /// Groups which have been started but not ended yet. Statements
+    /// are added to the innermost one.
+    #[serde(skip)]
+    open_groups: Vec<ExecutedStmt>,
 }
 
 impl Report {
@@ -38,6 +98,8 @@ impl Report {
         Self {
             date_time: Utc::now().format("%a %b %e %T %Y").to_string(),
             executed_stmts: vec![],
+            soft_failures: vec![],
+            open_groups: vec![],
             name,
             output_dir,
             num_screenshots: 0,
@@ -45,7 +107,47 @@ impl Report {
     }
 
     pub fn add_stmt(&mut self, es: ExecutedStmt) {
-        self.executed_stmts.push(es);
+        match self.open_groups.last_mut() {
+            Some(group) => group.children.push(es),
+            None => self.executed_stmts.push(es),
+        }
+    }
+
+    /// Starts a group, so the following statements are nested under it until end_group is called.
+    pub fn begin_group(&mut self, text: String) {
+        self.open_groups.push(ExecutedStmt::group(text));
+    }
+
+    /// Ends the innermost group, adding it to the report.
+    pub fn end_group(&mut self) {
+        if let Some(group) = self.open_groups.pop() {
+            self.add_stmt(group);
+        }
+    }
+
+    /// Ends any groups which are still open.
+    pub fn end_all_groups(&mut self) {
+        while !self.open_groups.is_empty() {
+            self.end_group();
+        }
+    }
+
+    /// The executed stmts in the order they ran, along with how deeply each one is nested.
+    pub fn flattened(&self) -> Vec<(usize, &ExecutedStmt)> {
+        fn flatten<'a>(
+            stmts: &'a [ExecutedStmt],
+            depth: usize,
+            out: &mut Vec<(usize, &'a ExecutedStmt)>,
+        ) {
+            for stmt in stmts {
+                out.push((depth, stmt));
+                flatten(&stmt.children, depth + 1, out);
+            }
+        }
+
+        let mut out = vec![];
+        flatten(&self.executed_stmts, 0, &mut out);
+        out
     }
 
     pub fn save_screenhots(&mut self) {
@@ -54,17 +156,16 @@ impl Report {
             "Could not create directory: {}",
             self.output_dir.display()
         ));
-        for stmt in self.executed_stmts.iter() {
+        let mut num_screenshots = self.num_screenshots;
+        for (_, stmt) in self.flattened() {
             for screenshot in stmt.screenshots.iter() {
-                self.num_screenshots += 1;
+                num_screenshots += 1;
                 let mut op = self.output_dir.clone();
-                op.push(format!(
-                    "{}_screenshot_{}.png",
-                    self.name, self.num_screenshots
-                ));
+                op.push(format!("{}_screenshot_{}.png", self.name, num_screenshots));
                 std::fs::write(op, screenshot).expect("Could not write screenshot");
             }
         }
+        self.num_screenshots = num_screenshots;
     }
 }
 